@@ -1,11 +1,110 @@
-use lang::{execution::ExecutionContext, parser::Parser};
+use lang::{execution::ExecutionContext, parser::Parser, validation};
 use miette::NamedSource;
 use std::{
     env,
     fs::{self},
+    thread,
+    time::Duration,
 };
 
+/// Used when `--max-call-depth` is not given, so runaway recursion is reported as an error.
+const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+/// Stack size of the interpreter thread. Large enough for `DEFAULT_MAX_CALL_DEPTH` calls in a
+/// debug build.
+const STACK_SIZE: usize = 512 * 1024 * 1024;
+
+const USAGE: &str = "\
+Usage: run [OPTIONS] <FILE>
+
+Options:
+        --emit-ast              Print the parsed module before running it
+        --warn-unused           Warn about variables that are never read
+        --max-call-depth <N>    Abort when function calls nest deeper than N [default: 1000]
+        --timeout <MS>          Abort when the program runs longer than MS milliseconds
+    -h, --help                  Print this help
+        --                      Treat everything after this as the input file";
+
+#[derive(Default)]
+struct Args {
+    input_file: Option<String>,
+    emit_ast: bool,
+    warn_unused: bool,
+    max_call_depth: Option<usize>,
+    timeout: Option<Duration>,
+    help: bool,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut result = Self::default();
+
+        while let Some(arg) = args.next() {
+            // Options can be given as `--name value` or `--name=value`
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) if arg.starts_with("--") => (name, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or(format!("Missing value for '{}'", name))
+            };
+
+            match name {
+                "-h" | "--help" => result.help = true,
+                "--emit-ast" => result.emit_ast = true,
+                "--warn-unused" => result.warn_unused = true,
+                "--max-call-depth" => {
+                    let depth = value()?;
+                    result.max_call_depth = Some(
+                        depth
+                            .parse()
+                            .map_err(|_| format!("Invalid call depth '{}'", depth))?,
+                    );
+                }
+                "--timeout" => {
+                    let millis = value()?;
+                    result.timeout = Some(Duration::from_millis(
+                        millis
+                            .parse()
+                            .map_err(|_| format!("Invalid timeout '{}'", millis))?,
+                    ));
+                }
+                "--" => {
+                    for file in args.by_ref() {
+                        result.set_input_file(file)?;
+                    }
+                }
+                flag if flag.starts_with('-') => return Err(format!("Unknown option '{}'", flag)),
+                _ => result.set_input_file(arg)?,
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn set_input_file(&mut self, file: String) -> Result<(), String> {
+        if self.input_file.is_some() {
+            return Err(format!("Unexpected argument '{}'", file));
+        }
+        self.input_file = Some(file);
+        Ok(())
+    }
+}
+
 fn main() {
+    // Every call in the interpreter uses native stack, so the main thread's stack is too small
+    let interpreter = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)
+        .expect("Spawning interpreter thread");
+    if interpreter.join().is_err() {
+        std::process::exit(101);
+    }
+}
+
+fn run() {
     miette::set_hook(Box::new(|_| {
         Box::new(
             miette::MietteHandlerOpts::new()
@@ -17,33 +116,63 @@ fn main() {
     }))
     .expect("Failed to set miette hook");
 
-    let mut args = env::args();
-    args.next(); // Skip exec path
-    let Some(input_file) = args.next() else {
-        eprintln!("You musst provide a file to run");
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if args.help {
+        println!("{}", USAGE);
         return;
+    }
+
+    let Some(input_file) = args.input_file else {
+        eprintln!("You musst provide a file to run\n\n{}", USAGE);
+        std::process::exit(2);
     };
 
     let input = fs::read_to_string(&input_file).expect("Reading source file");
-    /*let mut input_stream = Tokenizer::new(FileInputStream::new(file));
-        for tok in input_stream {
-        println!("{:?}", tok);
-    }*/
 
     let execution = Parser::new(input.as_str())
         .parse_module()
         .and_then(|module| {
-            let mut ctx = ExecutionContext::new(&module);
+            if args.emit_ast {
+                for func in module.value.functions() {
+                    println!("{}", func.value);
+                }
+            }
+            if args.warn_unused {
+                for warning in validation::find_unused_variables(&module.value) {
+                    eprintln!(
+                        "{:?}",
+                        miette::Report::new(warning)
+                            .with_source_code(NamedSource::new(&input_file, input.clone()))
+                    );
+                }
+            }
+
+            let mut ctx = ExecutionContext::new(&module)
+                .with_max_call_depth(args.max_call_depth.unwrap_or(DEFAULT_MAX_CALL_DEPTH));
+            if let Some(timeout) = args.timeout {
+                ctx = ctx.with_timeout(timeout);
+            }
             ctx.execute()
         });
 
     match execution {
-        Ok(_) => {}
+        Ok(value) => {
+            // main returns either void or an exit code
+            std::process::exit(value.value.as_int().unwrap_or(0) as i32);
+        }
         Err(e) => {
             eprintln!(
                 "{:?}",
                 e.with_source_code(NamedSource::new(input_file, input))
             );
+            std::process::exit(1);
         }
     };
 }
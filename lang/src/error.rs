@@ -1,6 +1,6 @@
-use std::fmt::Display;
+use std::{fmt::Display, time::Duration};
 
-use miette::{Diagnostic, SourceSpan};
+use miette::{Diagnostic, LabeledSpan, SourceSpan};
 use thiserror::Error;
 
 use crate::{
@@ -38,7 +38,6 @@ impl Display for ExpectedToken {
 
 #[derive(Error, Debug, Diagnostic)]
 #[error("Unexpected token '{found}'")]
-
 pub struct UnexpectedToken {
     pub found: Token,
 
@@ -48,6 +47,89 @@ pub struct UnexpectedToken {
     pub expected: ExpectedToken,
 }
 
+#[derive(Error, Debug, Diagnostic)]
+#[error("Expected `;` after statement")]
+#[diagnostic(help(
+    "statements in a block are separated by `;`, only the last expression may omit it"
+))]
+pub struct MissingSemicolon {
+    #[label("expected `;` here")]
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("Expected `;` or an operator between two expressions")]
+#[diagnostic(help(
+    "end the first expression with `;` or combine both expressions with an operator"
+))]
+pub struct MissingSeparator {
+    /// Start of the second expression
+    #[label("second expression starts here")]
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("Reserved keyword '{keyword}' used as identifier")]
+pub struct ReservedKeyword {
+    pub keyword: String,
+
+    #[label("reserved keyword")]
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("Unexpected end of input")]
+pub struct UnexpectedEof {
+    #[label("input ends here")]
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("Unexpected character '{found}'")]
+pub struct UnexpectedCharacter {
+    pub found: char,
+
+    #[label("here")]
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("Invalid number literal '{literal}'")]
+#[diagnostic(help("numbers are written like `42` or `4.2`"))]
+pub struct InvalidNumber {
+    pub literal: String,
+
+    #[label("here")]
+    pub span: SourceSpan,
+}
+
+/// A struct literal that misses fields or sets fields the struct doesn't have.
+/// Every offending field gets its own label.
+#[derive(Error, Debug, Diagnostic)]
+#[error("Invalid literal of struct '{name}'")]
+pub struct InvalidStructLiteral {
+    pub name: String,
+    pub missing: Vec<String>,
+    pub unknown: Vec<String>,
+
+    #[label(collection)]
+    pub labels: Vec<LabeledSpan>,
+}
+
+/// Warning for a `let` binding that is never read.
+#[derive(Error, Debug, Diagnostic)]
+#[error("Variable '{name}' is never read")]
+#[diagnostic(
+    severity(Warning),
+    help("remove the variable or start its name with `_`")
+)]
+pub struct UnusedVariable {
+    pub name: String,
+
+    #[label("declared here")]
+    pub span: SourceSpan,
+}
+
 #[derive(Error, Debug, Diagnostic)]
 #[error("Invalid number of arguments: found {found}, expected {expected}")]
 pub struct InvalidNumberOfArguments {
@@ -59,10 +141,21 @@ pub struct InvalidNumberOfArguments {
 }
 
 #[derive(Error, Debug, Diagnostic)]
-#[error("Invalid number of arguments: found {found}, expected {expected}")]
+#[error("main must return 'int' or 'void', found '{found}'")]
+pub struct InvalidMainReturnType {
+    pub found: TypeID,
+
+    #[label("here")]
+    pub span: SourceSpan,
+}
+
+#[derive(Error, Debug, Diagnostic)]
+#[error("Type mismatch: found {found}, expected {}", expected_type(.expected, .expected_alias))]
 pub struct TypeMismatch {
     pub found: TypeID,
     pub expected: TypeID,
+    /// The alias the expected type was declared with, e.g. `Id` for `type Id = int;`
+    pub expected_alias: Option<String>,
 
     #[source]
     #[diagnostic_source]
@@ -70,6 +163,17 @@ pub struct TypeMismatch {
 
     #[label("here")]
     pub span: SourceSpan,
+
+    /// Where the expected type was declared, e.g. the type of a function parameter
+    #[label("expected because of this")]
+    pub declared_span: Option<SourceSpan>,
+}
+
+fn expected_type(expected: &TypeID, alias: &Option<String>) -> String {
+    match alias {
+        Some(alias) => format!("{} (alias of {})", alias, expected),
+        None => expected.to_string(),
+    }
 }
 
 #[derive(Error, Debug, Diagnostic)]
@@ -82,6 +186,8 @@ pub enum TypeMismatchReason {
     BinaryOperation(BinaryOperator),
     #[error("Variable assignment")]
     VariableAssignment,
+    #[error("Index")]
+    Index,
 }
 
 #[derive(Error, Debug, Diagnostic)]
@@ -89,7 +195,89 @@ pub enum ControllFlow {
     #[error("Continue statement outside of loop")]
     Continue,
     #[error("Break statement outside of loop")]
-    Break,
+    Break(Value),
     #[error("Return statement outside of function")]
     Return(Value),
 }
+
+#[derive(Error, Debug, Diagnostic)]
+pub enum LimitExceeded {
+    #[error("Maximum call depth of {limit} exceeded")]
+    CallDepth {
+        limit: usize,
+
+        #[label("here")]
+        span: SourceSpan,
+    },
+    #[error("Execution took longer than {limit:?}")]
+    Timeout {
+        limit: Duration,
+
+        #[label("here")]
+        span: SourceSpan,
+    },
+}
+
+#[derive(Error, Debug, Diagnostic)]
+pub enum InvalidControllFlow {
+    #[error("Continue statement outside of loop")]
+    Continue {
+        #[label("here")]
+        span: SourceSpan,
+    },
+    #[error("Break statement outside of loop")]
+    Break {
+        #[label("here")]
+        span: SourceSpan,
+    },
+}
+
+#[derive(Error, Debug, Diagnostic)]
+pub enum Redefinition {
+    #[error("Function '{name}' is defined multiple times")]
+    Function {
+        name: String,
+
+        #[label("first defined here")]
+        first: SourceSpan,
+        #[label("redefined here")]
+        second: SourceSpan,
+    },
+    #[error("Struct '{name}' is defined multiple times")]
+    Struct {
+        name: String,
+
+        #[label("first defined here")]
+        first: SourceSpan,
+        #[label("redefined here")]
+        second: SourceSpan,
+    },
+    #[error("Type '{name}' is defined multiple times")]
+    Type {
+        name: String,
+
+        #[label("first defined here")]
+        first: SourceSpan,
+        #[label("redefined here")]
+        second: SourceSpan,
+    },
+    #[error("Method '{name}' is defined multiple times for '{type_name}'")]
+    Method {
+        type_name: String,
+        name: String,
+
+        #[label("first defined here")]
+        first: SourceSpan,
+        #[label("redefined here")]
+        second: SourceSpan,
+    },
+    #[error("Module '{name}' is defined multiple times")]
+    Module {
+        name: String,
+
+        #[label("first defined here")]
+        first: SourceSpan,
+        #[label("redefined here")]
+        second: SourceSpan,
+    },
+}
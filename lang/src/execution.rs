@@ -1,11 +1,17 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 
 use miette::{miette, Context, Error, LabeledSpan, SourceSpan};
 /// This Module is used to execute a program.
 use value::Value;
 
 use crate::{
-    error::{ControllFlow, InvalidNumberOfArguments, TypeMismatch, TypeMismatchReason},
+    error::{
+        ControllFlow, InvalidMainReturnType, InvalidNumberOfArguments, InvalidStructLiteral,
+        LimitExceeded, TypeMismatch, TypeMismatchReason,
+    },
     module::Module,
     parser::{
         binary_expression::{BinaryExpression, BinaryOperator},
@@ -13,25 +19,83 @@ use crate::{
         function::FunctionDecl,
         structs::StructValue,
         type_def::{TypeDef, TypeID},
+        Parser,
     },
     spanned::{SpanExt, Spanned},
     system_functions::{self, IntoSystem, System},
     tokenizer::literal::Literal,
-    ALResult,
+    validation, ALResult,
 };
 
+mod inference;
 pub mod value;
 
 pub struct ExecutionContext<'a> {
     pub span: SourceSpan,
     pub scopes: Vec<Scope>,
-    pub public_functions: Vec<&'a Spanned<FunctionDecl>>,
+    /// The root module
+    pub module: &'a Module,
     pub public_types: HashMap<String, Spanned<TypeDef>>,
     pub system_functions: Vec<(String, Box<dyn System>)>,
+
+    /// Maximum number of nested function calls. `None` means unlimited.
+    /// Every call uses native stack, so the limit has to fit the stack of the running thread:
+    /// a debug build needs tens of KiB per call, a release build much less.
+    pub max_call_depth: Option<usize>,
+    /// Maximum wall-clock time for [`ExecutionContext::execute`]. `None` means unlimited.
+    pub timeout: Option<Duration>,
+    call_depth: usize,
+    deadline: Option<Instant>,
+    /// Declared return types of the functions that are currently running, innermost last.
+    /// Aliases are not resolved, so errors can name them.
+    return_types: Vec<Option<Spanned<TypeID>>>,
+    /// Return types of the functions without `->`, inferred before `main` runs.
+    /// `None` if the type is only known while running.
+    inferred_return_types: HashMap<*const Spanned<FunctionDecl>, Option<Spanned<TypeID>>>,
+    /// Nested modules around the running function, outermost first.
+    /// Empty while a function of the root module runs.
+    module_path: Vec<&'a Module>,
 }
 
 pub struct Scope {
-    pub variables: Vec<Spanned<(String, Value)>>,
+    pub variables: Vec<Spanned<(String, Variable)>>,
+}
+
+/// Where a variable lives: the index of its scope and its index inside of that scope.
+/// Unlike a reference it doesn't borrow the context, so other expressions can run between
+/// reading and writing the variable.
+#[derive(Debug, Clone, Copy)]
+struct VarLocation {
+    scope: usize,
+    index: usize,
+}
+
+/// A variable declared with `let x: T;` stays uninitialized until the first assignment.
+pub enum Variable {
+    Initialized(Value),
+    Uninitialized(TypeID),
+}
+
+impl Variable {
+    /// Assigns a new value to the variable. The type of the value must match the variable type.
+    pub fn assign(&mut self, value: &Spanned<Value>) -> ALResult<()> {
+        match self {
+            Variable::Initialized(current) => current.set_value(value),
+            Variable::Uninitialized(type_id) if *type_id == value.value.type_id() => {
+                *self = Variable::Initialized(value.value.clone());
+                Ok(Spanned::new((), value.span))
+            }
+            Variable::Uninitialized(type_id) => Err(TypeMismatch {
+                found: value.value.type_id(),
+                expected: type_id.clone(),
+                reason: TypeMismatchReason::VariableAssignment,
+                expected_alias: None,
+                declared_span: None,
+                span: value.span,
+            })
+            .wrap_err("Setting value"),
+        }
+    }
 }
 
 impl<'a> ExecutionContext<'a> {
@@ -41,7 +105,7 @@ impl<'a> ExecutionContext<'a> {
                 variables: Vec::new(),
             }],
             span: module.span,
-            public_functions: module.value.functions().iter().collect(),
+            module: &module.value,
             system_functions: Vec::with_capacity(4),
             public_types: module
                 .value
@@ -49,9 +113,32 @@ impl<'a> ExecutionContext<'a> {
                 .iter()
                 .map(|s| (s.0.value.clone(), s.1.clone().map_value(TypeDef::Struct)))
                 .collect(),
+            max_call_depth: None,
+            timeout: None,
+            call_depth: 0,
+            deadline: None,
+            return_types: Vec::new(),
+            inferred_return_types: HashMap::new(),
+            module_path: Vec::new(),
         }
         .register_system_function("print", system_functions::print::print)
         .register_system_function("println", system_functions::print::println)
+        .register_system_function("typeof", system_functions::reflect::type_of)
+        .register_system_function("format", system_functions::format::Format)
+    }
+
+    /// Parses `source` and runs its `main` function with the default system functions.
+    /// Errors don't carry the source, attach it with `with_source_code` before printing them.
+    /// # Example
+    /// ```rust
+    /// use lang::prelude::*;
+    ///
+    /// let result = ExecutionContext::run_source("fn main() -> int { 40 + 2 }").unwrap();
+    /// assert_eq!(result.value.as_int(), Some(42));
+    /// ```
+    pub fn run_source(source: &str) -> ALResult<Value> {
+        let module = Parser::new(source).parse_module()?;
+        ExecutionContext::new(&module).execute()
     }
 
     pub fn register_system_function<I, S: System + 'static>(
@@ -59,17 +146,53 @@ impl<'a> ExecutionContext<'a> {
         name: impl Into<String>,
         system: impl IntoSystem<I, System = S>,
     ) -> Self {
+        let name = name.into();
+        // Registering a function twice replaces the old one. This way the defaults can be overwritten.
+        self.system_functions.retain(|(n, _)| *n != name);
         self.system_functions
-            .push((name.into(), Box::new(system.into_system())));
+            .push((name, Box::new(system.into_system())));
+        self
+    }
+
+    /// Makes a type defined in Rust available to the program under `name`.
+    /// Together with [`Value::new_opaque`] this lets system functions pass Rust values through
+    /// the program.
+    pub fn register_type(mut self, name: impl Into<String>, type_def: TypeDef) -> Self {
+        self.public_types.insert(name.into(), type_def.into());
+        self
+    }
+
+    /// Limits the number of nested function calls, so runaway recursion
+    /// results in an error instead of a stack overflow.
+    pub fn with_max_call_depth(mut self, max_call_depth: usize) -> Self {
+        self.max_call_depth = Some(max_call_depth);
+        self
+    }
+
+    /// Aborts the execution once it runs longer than `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
         self
     }
 
     pub fn execute(&mut self) -> ALResult<Value> {
-        let func_name = if let Some(main) = self
-            .public_functions
-            .iter_mut()
-            .find(|func| func.value.proto.value.name.value == "main")
-        {
+        validation::validate_module(self.module)?;
+        self.infer_return_types(Vec::new())?;
+
+        self.deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+
+        let func_name = if let Some(main) = self.module.find_function("main") {
+            // main can only return an exit code or nothing
+            if let Some(return_type) = self.return_type(Vec::new(), main)? {
+                if !matches!(return_type.value, TypeID::Int | TypeID::Void) {
+                    return Err(InvalidMainReturnType {
+                        found: return_type.value,
+                        span: return_type.span,
+                    }
+                    .into());
+                }
+            }
+
             main.value.proto.value.name.clone()
         } else {
             return Err(miette!("No main function found"));
@@ -89,55 +212,146 @@ impl<'a> ExecutionContext<'a> {
             .map(|arg| self.run_expr(arg))
             .collect::<Vec<_>>();
 
+        self.call_function(func_name, input_values)
+    }
+
+    /// Runs `receiver.name(args)`. A method in an `impl` block of the receiver type is preferred,
+    /// otherwise this is the same as `name(receiver, args)`.
+    fn run_method(
+        &mut self,
+        func_name: Spanned<String>,
+        receiver: Spanned<Value>,
+        args: &[Spanned<Expr>],
+    ) -> ALResult<Value> {
+        let receiver_type = receiver.value.type_id();
+        let input_values = std::iter::once(Ok(receiver))
+            .chain(args.iter().map(|arg| self.run_expr(arg)))
+            .collect::<Vec<_>>();
+
+        let method = match receiver_type {
+            TypeID::User(type_name) => self.find_method(&type_name, &func_name.value),
+            _ => None,
+        };
+
+        match method {
+            Some((module_path, method)) => {
+                self.run_declared_function(func_name.span, module_path, method, input_values)
+            }
+            None => self.call_function(func_name, input_values),
+        }
+    }
+
+    /// Calls a system function or a function of the module with already evaluated arguments.
+    fn call_function(
+        &mut self,
+        func_name: Spanned<String>,
+        input_values: Vec<ALResult<Value>>,
+    ) -> ALResult<Value> {
         // Find the function to call
         let system_function = self
             .system_functions
             .iter()
             .find(|f| f.0 == func_name.value);
 
-        let function = self
-            .public_functions
-            .iter()
-            .find(|func| func.value.proto.value.name.value == func_name.value);
+        let function = self.find_function(&func_name.value);
 
         match (system_function, function) {
             (Some(func), _) => self.run_system_function(func_name, func.1.as_ref(), input_values),
-            (None, Some(func)) => self.run_declared_function(func_name.span, func, input_values),
+            (None, Some((module_path, func))) => {
+                self.run_declared_function(func_name.span, module_path, func, input_values)
+            }
             (None, None) => Err(miette!("Function '{}' not found", func_name.value)),
         }
     }
 
+    /// Resolves a path like `inner::helper` against the module of the running function first,
+    /// then against the modules around it up to the root.
+    /// Returns the function together with the path of nested modules it is declared in.
+    fn find_function(&self, path: &str) -> Option<(Vec<&'a Module>, &'a Spanned<FunctionDecl>)> {
+        (0..=self.module_path.len()).rev().find_map(|depth| {
+            let mut module_path = self.module_path[..depth].to_vec();
+            let mut module = module_path.last().copied().unwrap_or(self.module);
+            let (modules, name) = match path.rsplit_once("::") {
+                Some((modules, name)) => (Some(modules), name),
+                None => (None, path),
+            };
+            for segment in modules.into_iter().flat_map(|modules| modules.split("::")) {
+                module = &module.find_module(segment)?.1.value;
+                module_path.push(module);
+            }
+            module
+                .find_function(name)
+                .map(|function| (module_path, function))
+        })
+    }
+
+    /// Like functions, methods are looked up in the running module first.
+    /// Returns the method together with the path of nested modules it is declared in.
+    fn find_method(
+        &self,
+        type_name: &str,
+        name: &str,
+    ) -> Option<(Vec<&'a Module>, &'a Spanned<FunctionDecl>)> {
+        (0..=self.module_path.len()).rev().find_map(|depth| {
+            let module = self.module_path[..depth].last().copied();
+            module
+                .unwrap_or(self.module)
+                .find_method(type_name, name)
+                .map(|method| (self.module_path[..depth].to_vec(), method))
+        })
+    }
+
     fn run_system_function(
         &self,
         call_span: Spanned<String>,
         system: &dyn System,
         arguments: Vec<ALResult<Value>>,
     ) -> ALResult<Value> {
-        // Check for provided arguments
-        /*if proto.arguments.value.len() != arguments.len() {
-            return Err(Error::new_invalid_number_of_arguments(
-                call_span.span,
-                proto.arguments.value.len(),
-                arguments.len(),
-            ));
-        }*/
+        self.check_timeout(call_span.span)?;
 
-        let result = system.run(
-            arguments
-                .into_iter()
-                .map(|arg| arg.map(|v| v.value))
-                .collect::<Result<Vec<_>, Error>>()?,
-        );
+        // Check for provided arguments. Variadic functions only need the leading ones.
+        let arg_count_matches = match system.is_variadic() {
+            true => arguments.len() >= system.arg_count(),
+            false => arguments.len() == system.arg_count(),
+        };
+        if !arg_count_matches {
+            return Err(InvalidNumberOfArguments {
+                found: arguments.len(),
+                expected: system.arg_count(),
+                span: call_span.span,
+            }
+            .into());
+        }
+
+        let result = system
+            .run(
+                arguments
+                    .into_iter()
+                    .map(|arg| arg.map(|v| v.value))
+                    .collect::<Result<Vec<_>, Error>>()?,
+            )
+            .map_err(|err| {
+                miette!(
+                    labels = vec![LabeledSpan::at(call_span.span, "here")],
+                    "{}",
+                    err
+                )
+            })?;
 
         Ok(Spanned::new(result, call_span.span))
     }
 
+    /// Runs `function`, which is declared in the nested module at the end of `module_path`.
+    /// Names in the body are resolved against that module and the modules around it.
     fn run_declared_function(
         &mut self,
         call_span: SourceSpan,
+        module_path: Vec<&'a Module>,
         function: &Spanned<FunctionDecl>,
         arguments: Vec<ALResult<Value>>,
     ) -> ALResult<Value> {
+        self.check_timeout(call_span)?;
+
         // Check for provided arguments
         if function.value.proto.value.arguments.value.len() != arguments.len() {
             return Err(InvalidNumberOfArguments {
@@ -148,12 +362,43 @@ impl<'a> ExecutionContext<'a> {
             .into());
         }
 
+        if let Some(limit) = self
+            .max_call_depth
+            .filter(|&limit| self.call_depth >= limit)
+        {
+            return Err(LimitExceeded::CallDepth {
+                limit,
+                span: call_span,
+            }
+            .into());
+        }
+
         // Create a new scope for the function
         let mut scope = Scope {
             variables: Vec::new(),
         };
 
-        let return_type = function.value.proto.value.return_type.value.clone();
+        // Arguments can fail or break out of a loop around the call. This has to happen before
+        // the module path is swapped, or it would stay on the callee's modules.
+        let arguments = arguments.into_iter().collect::<Result<Vec<_>, _>>()?;
+
+        // Types in the signature are resolved against the module of the function
+        let caller_module_path = std::mem::replace(&mut self.module_path, module_path);
+
+        let key: *const Spanned<FunctionDecl> = function;
+        let declared_return_type = function
+            .value
+            .proto
+            .value
+            .return_type
+            .clone()
+            .or_else(|| self.inferred_return_types.get(&key).cloned().flatten());
+        let return_type = declared_return_type
+            .as_ref()
+            .map(|return_type| self.resolve_type(&return_type.value));
+        let return_alias = declared_return_type
+            .as_ref()
+            .and_then(|return_type| self.alias_name(&return_type.value));
 
         // Push input vars to the function stack
         for ((arg_name, arg_type), value) in function
@@ -165,12 +410,16 @@ impl<'a> ExecutionContext<'a> {
             .iter()
             .zip(arguments)
         {
-            let value = value?;
-            if value.value.type_id != arg_type.value {
+            let expected = self.resolve_type(&arg_type.value);
+            if value.value.type_id() != expected {
+                let expected_alias = self.alias_name(&arg_type.value);
+                self.module_path = caller_module_path;
                 return Err(TypeMismatch {
-                    found: value.value.type_id.clone(),
-                    expected: arg_type.value.clone(),
+                    found: value.value.type_id(),
+                    expected,
                     reason: TypeMismatchReason::FunctionArgument,
+                    expected_alias,
+                    declared_span: Some(arg_type.span),
                     span: value.span,
                 }
                 .into());
@@ -178,30 +427,37 @@ impl<'a> ExecutionContext<'a> {
 
             // Make spanned tuple of the variable name and the value
             // The Span will be the span of the expression which is the input for the function call
-            let value = value.map_value(|val| (arg_name.value.clone(), val));
+            let value = value.map_value(|val| (arg_name.value.clone(), Variable::Initialized(val)));
 
             scope.variables.push(value);
         }
 
-        // Push scope for the body
-        self.scopes.push(scope);
+        // The body gets its own scope stack, so it can't see the locals of the caller
+        let caller_scopes = std::mem::replace(&mut self.scopes, vec![scope]);
+        self.call_depth += 1;
+        self.return_types.push(declared_return_type);
 
         let res = self.run_expr(&function.value.body).or_else(|err| {
             match err.downcast_ref::<ControllFlow>() {
                 Some(ControllFlow::Return(val)) => Ok(Spanned::new(val.clone(), call_span)),
                 _ => Err(err),
             }
-        })?;
+        });
 
-        // Pop the scope
-        self.scopes.pop();
+        self.call_depth -= 1;
+        self.return_types.pop();
+        self.scopes = caller_scopes;
+        self.module_path = caller_module_path;
+        let res = res?;
 
-        if res.value.type_id != return_type {
+        if let Some(return_type) = return_type.filter(|t| *t != res.value.type_id()) {
             // Return types dont match
             return Err(TypeMismatch {
-                found: res.value.type_id.clone(),
+                found: res.value.type_id(),
                 expected: return_type,
                 reason: TypeMismatchReason::FunctionReturn,
+                expected_alias: return_alias,
+                declared_span: None,
                 span: res.span,
             }
             .into());
@@ -216,33 +472,31 @@ impl<'a> ExecutionContext<'a> {
                 let lhs = self.run_expr(lhs)?;
                 match &rhs.value {
                     DotExpr::Variable(name) => {
-                        let type_def =
-                            self.find_type_def(&lhs.clone().map_value(|value| value.type_id))?;
-                        match type_def.value {
-                            TypeDef::Struct(strct) => {
-                                strct.fields.iter().position(|f| f.value.0 == name.value).map(
-                                    |index| lhs
-                                                    .value
-                                                    .as_struct()
-                                                    .expect("Value ist not a struct. Can't happen, because we check if type is struct")
-                                                    .get_field(index)
-                                                    .expect("Field must exist. Or we try to access wrong struct")
-                                                    .clone()
-                                ).ok_or(miette!(
-                                    labels = vec![LabeledSpan::at(expr.span, "here")],
-                                    "Field not found",
-                                ))
-                            }
-                            _ => Err(miette!("Can't access field of non-struct type")),
-                        }
+                        let Some(struct_value) = lhs.value.as_struct() else {
+                            return Err(miette!(
+                                labels = vec![LabeledSpan::at(lhs.span, "here")],
+                                "Can't access field of non-struct type '{}'",
+                                lhs.value.type_id()
+                            ));
+                        };
+
+                        struct_value
+                            .field_by_name(&name.value)
+                            .cloned()
+                            .ok_or(miette!(
+                                labels = vec![LabeledSpan::at(expr.span, "here")],
+                                "Field not found",
+                            ))
+                    }
+                    DotExpr::FunctionCall(name, args) => {
+                        self.run_method(name.map_span(|_| expr.span), lhs, args)
                     }
-                    _ => unimplemented!(),
                 }
             }
             Expr::FunctionCall(name, args) => self.run_function(name.map_span(|_| expr.span), args),
             Expr::Variable(name) => {
-                let var = self.find_var(name)?;
-                Ok(Spanned::new(var.value.clone(), name.span))
+                let location = self.find_var(name)?.value;
+                self.read_var(name, location)
             }
             Expr::Literal(literal) => match &literal.value {
                 Literal::NumberInt(val) => Ok(Spanned::new(Value::new_int(*val), literal.span)),
@@ -253,59 +507,13 @@ impl<'a> ExecutionContext<'a> {
                 Literal::Bool(val) => Ok(Spanned::new(Value::new_bool(*val), literal.span)),
             },
             Expr::StructLiteral(name, field_inits) => {
-                let Spanned::<TypeDef> { value, .. } =
-                    self.find_type_def(&name.clone().map_value(TypeID::User))?;
-
-                let TypeDef::Struct(struct_def) = value else {
-                    return Err(miette!(
-                        labels = vec![LabeledSpan::at(name.span, "here")],
-                        "Type is not a struct",
-                    ));
-                };
-
-                let mut struct_value = StructValue::default();
-                for struct_def_field in struct_def.fields.iter() {
-                    let field = field_inits
-                        .iter()
-                        .find(|f| f.0.value == struct_def_field.value.0)
-                        .map(|f| self.run_expr(&f.1))
-                        .ok_or(miette!(
-                            labels = vec![LabeledSpan::at(name.span, "here")],
-                            "Field not initialized",
-                        ))??;
-
-                    // Handle invalid type
-                    if field.value.type_id != struct_def_field.value.1 {
-                        return Err(TypeMismatch {
-                            found: field.value.type_id.clone(),
-                            expected: struct_def_field.value.1.clone(),
-                            reason: TypeMismatchReason::FunctionArgument,
-                            span: field.span,
-                        })
-                        .wrap_err("Field initialization");
-                    }
-                    struct_value.push_field(field);
-                }
-                // Check if we try to initialize a field that is not in the struct
-                for field in field_inits {
-                    if !struct_def.fields.iter().any(|f| f.value.0 == field.0.value) {
-                        return Err(miette!(
-                            labels = vec![LabeledSpan::at(field.0.span, "here")],
-                            "Field not found",
-                        ));
-                    }
-                }
-
-                Ok(Spanned::new(
-                    Value::new_struct(name.value.clone(), struct_value),
-                    expr.span,
-                ))
+                self.run_struct_literal(expr.span, name, field_inits)
             }
             Expr::Assignment(var, expr) => {
                 let val = self.run_expr(expr)?;
-                let var = self.find_var(var)?;
+                let location = self.find_var(var)?.value;
 
-                var.value.set_value(&val)?;
+                self.var_mut(location).assign(&val)?;
                 Ok(Spanned::new(val.value, val.span))
             }
             Expr::Let(var_name, type_id, assign) => {
@@ -324,27 +532,38 @@ impl<'a> ExecutionContext<'a> {
                     ));
                 }
 
-                let span = assign.span;
+                let declared_type = type_id.as_ref();
+                let type_id = declared_type.map(|type_id| self.resolve_type(&type_id.value));
 
-                let value = self.run_expr(assign)?.value;
+                let (variable, span) = match (assign, type_id) {
+                    (Some(assign), type_id) => {
+                        let value = self.run_expr(assign)?.value;
 
-                if let Some(type_id) = type_id {
-                    if value.type_id != type_id.value {
-                        return Err(TypeMismatch {
-                            found: value.type_id.clone(),
-                            expected: type_id.value.clone(),
-                            reason: TypeMismatchReason::VariableAssignment,
-                            span,
+                        if let Some(type_id) = type_id {
+                            if value.type_id() != type_id {
+                                return Err(TypeMismatch {
+                                    found: value.type_id(),
+                                    expected: type_id,
+                                    reason: TypeMismatchReason::VariableAssignment,
+                                    expected_alias: declared_type
+                                        .and_then(|declared| self.alias_name(&declared.value)),
+                                    declared_span: None,
+                                    span: assign.span,
+                                }
+                                .into());
+                            }
                         }
-                        .into());
+                        (Variable::Initialized(value), assign.span)
                     }
-                }
+                    // The parser only allows a missing initializer with a type annotation
+                    (None, Some(type_id)) => (Variable::Uninitialized(type_id), expr.span),
+                    (None, None) => unreachable!("let without initializer and type"),
+                };
 
-                self.scopes
-                    .last_mut()
-                    .unwrap()
-                    .variables
-                    .push(Spanned::new((var_name.value.clone(), value), var_name.span));
+                self.scopes.last_mut().unwrap().variables.push(Spanned::new(
+                    (var_name.value.clone(), variable),
+                    var_name.span,
+                ));
 
                 Ok(Spanned::new(Value::new_void(), span))
             }
@@ -352,13 +571,9 @@ impl<'a> ExecutionContext<'a> {
                 value: BinaryExpression { lhs, op, rhs },
                 ..
             }) => {
-                if matches!(op.value, BinaryOperator::Assign) {
+                if op.value.is_assignment() {
                     if let Expr::Variable(lhs_var) = &lhs.value {
-                        let rhs = self.run_expr(rhs)?;
-                        let var = self.find_var(lhs_var)?;
-
-                        var.value.set_value(&rhs)?;
-                        return Ok(Spanned::new(rhs.value, expr.span));
+                        return self.run_assignment(expr.span, lhs_var, &op.value, rhs);
                     } else {
                         return Err(miette!(
                             labels = vec![LabeledSpan::at(lhs.span, "here")],
@@ -370,23 +585,11 @@ impl<'a> ExecutionContext<'a> {
                 let lhs = self.run_expr(lhs)?;
                 let rhs = self.run_expr(rhs)?;
 
-                match op.value {
-                    BinaryOperator::Add => lhs.value.add(&rhs),
-                    BinaryOperator::Substract => lhs.value.sub(&rhs),
-                    BinaryOperator::Multiply => lhs.value.mul(&rhs),
-                    BinaryOperator::Divide => lhs.value.div(&rhs),
-                    BinaryOperator::And => lhs.value.and(&rhs),
-                    BinaryOperator::Or => lhs.value.or(&rhs),
-                    BinaryOperator::Equal => lhs.value.eq(&rhs),
-                    BinaryOperator::NotEqual => lhs.value.neq(&rhs),
-                    BinaryOperator::LessThan => lhs.value.lt(&rhs),
-                    BinaryOperator::LessThanOrEqual => lhs.value.lte(&rhs),
-                    BinaryOperator::GreaterThan => lhs.value.gt(&rhs),
-                    BinaryOperator::GreaterThanOrEqual => lhs.value.gte(&rhs),
-                    // Assign already covered
-                    _ => unreachable!(),
-                }
-                .map(|v| v.map_span(|_| lhs.span.union(&rhs.span)))
+                run_binary_operator(&op.value, &lhs, &rhs)
+                    .map(|v| v.map_span(|_| lhs.span.union(&rhs.span)))
+            }
+            Expr::ChainedComparison(first, comparisons) => {
+                self.run_chained_comparison(first, comparisons)
             }
             Expr::IfExpression {
                 if_block: (condition, then_block),
@@ -395,9 +598,11 @@ impl<'a> ExecutionContext<'a> {
             } => {
                 let condition = self.run_expr(condition)?;
                 let value = condition.value.as_bool().ok_or(TypeMismatch {
-                    found: condition.value.type_id.clone(),
+                    found: condition.value.type_id(),
                     expected: TypeID::Bool,
                     reason: TypeMismatchReason::FunctionArgument,
+                    expected_alias: None,
+                    declared_span: None,
                     span: condition.span,
                 })?;
 
@@ -435,13 +640,15 @@ impl<'a> ExecutionContext<'a> {
             }
 
             Expr::Loop(expr) => loop {
+                self.check_timeout(expr.span)?;
+
                 match self.run_expr(expr) {
                     Ok(_) => {}
                     Err(err) => {
                         let flow = err.downcast_ref::<ControllFlow>();
                         match flow {
-                            Some(ControllFlow::Break) => {
-                                break Ok(Spanned::new(Value::new_void(), expr.span))
+                            Some(ControllFlow::Break(value)) => {
+                                break Ok(Spanned::new(value.clone(), expr.span))
                             }
                             Some(ControllFlow::Continue) => continue,
                             _ => return Err(err),
@@ -450,15 +657,128 @@ impl<'a> ExecutionContext<'a> {
                 }
             },
 
+            Expr::For {
+                var,
+                iterable,
+                body,
+            } => {
+                let iterable = self.run_expr(iterable)?;
+                let Some(chars) = iterable
+                    .value
+                    .as_string()
+                    .map(|s| s.chars().collect::<Vec<_>>())
+                else {
+                    return Err(miette!(
+                        labels = vec![LabeledSpan::at(iterable.span, "here")],
+                        "Can't iterate over value of type '{}'",
+                        iterable.value.type_id()
+                    ));
+                };
+
+                for c in chars {
+                    self.check_timeout(body.span)?;
+
+                    // Every iteration gets its own scope for the loop variable
+                    self.scopes.push(Scope {
+                        variables: vec![Spanned::new(
+                            (
+                                var.value.clone(),
+                                Variable::Initialized(Value::new_string(c.to_string())),
+                            ),
+                            var.span,
+                        )],
+                    });
+                    let result = self.run_expr(body);
+                    self.scopes.pop();
+
+                    if let Err(err) = result {
+                        match err.downcast_ref::<ControllFlow>() {
+                            Some(ControllFlow::Break(_)) => break,
+                            Some(ControllFlow::Continue) => continue,
+                            _ => return Err(err),
+                        }
+                    }
+                }
+
+                Ok(Spanned::new(Value::new_void(), expr.span))
+            }
+            Expr::Cast {
+                expr: value,
+                target,
+            } => {
+                let value = self.run_expr(value)?;
+                self.run_cast(value, target)
+                    .map(|cast| cast.map_span(|_| expr.span))
+            }
+            Expr::Index {
+                expr: indexed,
+                index: index_expr,
+            } => {
+                let value = self.run_expr(indexed)?;
+                let index = self.run_expr(index_expr)?;
+                let index = index.value.as_int().ok_or(TypeMismatch {
+                    found: index.value.type_id(),
+                    expected: TypeID::Int,
+                    reason: TypeMismatchReason::Index,
+                    expected_alias: None,
+                    declared_span: None,
+                    span: index.span,
+                })?;
+
+                let Some(string) = value.value.as_string() else {
+                    return Err(miette!(
+                        labels = vec![LabeledSpan::at(indexed.span, "here")],
+                        "Can't index value of type '{}'",
+                        value.value.type_id()
+                    ));
+                };
+
+                // Index by unicode scalar values, not by bytes
+                usize::try_from(index)
+                    .ok()
+                    .and_then(|index| string.chars().nth(index))
+                    .map(|c| Spanned::new(Value::new_string(c.to_string()), expr.span))
+                    .ok_or(miette!(
+                        labels = vec![LabeledSpan::at(index_expr.span, "here")],
+                        "Index {} is out of bounds for a string of length {}",
+                        index,
+                        string.chars().count()
+                    ))
+            }
             Expr::Return(ret_val) => {
                 let value = ret_val
                     .as_ref()
                     .map(|e| self.run_expr(e))
                     .transpose()?
                     .unwrap_or(Spanned::new(Value::new_void(), expr.span));
+
+                // Check the type here, so the error points at the return and not at the call
+                if let Some(Some(declared)) = self.return_types.last() {
+                    let expected = self.resolve_type(&declared.value);
+                    if value.value.type_id() != expected {
+                        return Err(TypeMismatch {
+                            found: value.value.type_id(),
+                            expected,
+                            reason: TypeMismatchReason::FunctionReturn,
+                            expected_alias: self.alias_name(&declared.value),
+                            declared_span: Some(declared.span),
+                            span: value.span,
+                        }
+                        .into());
+                    }
+                }
+
                 Err(ControllFlow::Return(value.value).into())
             }
-            Expr::Break => Err(ControllFlow::Break.into()),
+            Expr::Break(value) => {
+                let value = value
+                    .as_ref()
+                    .map(|e| self.run_expr(e))
+                    .transpose()?
+                    .map(|value| value.value)
+                    .unwrap_or(Value::new_void());
+                Err(ControllFlow::Break(value).into())
+            }
             Expr::Continue => Err(ControllFlow::Continue.into()),
         }
     }
@@ -466,15 +786,37 @@ impl<'a> ExecutionContext<'a> {
 
 // Helpers
 impl ExecutionContext<'_> {
-    fn find_var(&mut self, name: &Spanned<String>) -> ALResult<&mut Value> {
-        for scope in self.scopes.iter_mut().rev() {
-            if let Some(value) = scope.variables.iter_mut().find_map(
-                |Spanned::<(String, Value)> {
-                     value: (n, v),
-                     span,
-                 }| (n == &name.value).then_some(Spanned::new(v, *span)),
+    fn check_timeout(&self, span: SourceSpan) -> ALResult<()> {
+        match (self.deadline, self.timeout) {
+            (Some(deadline), Some(limit)) if Instant::now() > deadline => {
+                Err(LimitExceeded::Timeout { limit, span }.into())
+            }
+            _ => Ok(Spanned::new((), span)),
+        }
+    }
+
+    /// Looks up the variable in the innermost scope that declares it.
+    /// The span is the span of the declaration.
+    fn find_var(&self, name: &Spanned<String>) -> ALResult<VarLocation> {
+        for (scope_index, scope) in self.scopes.iter().enumerate().rev() {
+            if let Some(location) = scope.variables.iter().enumerate().find_map(
+                |(
+                    index,
+                    Spanned::<(String, Variable)> {
+                        value: (n, _),
+                        span,
+                    },
+                )| {
+                    (n == &name.value).then_some(Spanned::new(
+                        VarLocation {
+                            scope: scope_index,
+                            index,
+                        },
+                        *span,
+                    ))
+                },
             ) {
-                return Ok(value);
+                return Ok(location);
             }
         }
 
@@ -484,8 +826,192 @@ impl ExecutionContext<'_> {
         ))
     }
 
+    fn var_mut(&mut self, location: VarLocation) -> &mut Variable {
+        &mut self.scopes[location.scope].variables[location.index]
+            .value
+            .1
+    }
+
+    /// Reads the value of the variable `name` refers to. Fails if it was never assigned.
+    fn read_var(&self, name: &Spanned<String>, location: VarLocation) -> ALResult<Value> {
+        match &self.scopes[location.scope].variables[location.index]
+            .value
+            .1
+        {
+            Variable::Initialized(value) => Ok(Spanned::new(value.clone(), name.span)),
+            Variable::Uninitialized(_) => Err(miette!(
+                labels = vec![LabeledSpan::at(name.span, "here")],
+                "Variable '{}' is used before it was assigned",
+                name.value
+            )),
+        }
+    }
+
+    /// Runs `var = rhs` or a compound assignment like `var += rhs`.
+    /// For a compound assignment the variable is read before the right hand side runs.
+    fn run_assignment(
+        &mut self,
+        span: SourceSpan,
+        var: &Spanned<String>,
+        op: &BinaryOperator,
+        rhs: &Spanned<Expr>,
+    ) -> ALResult<Value> {
+        let location = self.find_var(var)?.value;
+        let value = match op.compound_operator() {
+            Some(op) => {
+                let current = self.read_var(var, location)?;
+                let rhs = self.run_expr(rhs)?;
+                run_binary_operator(&op, &current, &rhs)?.map_span(|_| rhs.span)
+            }
+            None => self.run_expr(rhs)?,
+        };
+
+        self.var_mut(location).assign(&value)?;
+        Ok(Spanned::new(value.value, span))
+    }
+
+    /// Replaces type aliases with the type they stand for.
+    /// Aliases of the running module shadow the ones of the modules around it.
+    fn resolve_type(&self, type_id: &TypeID) -> TypeID {
+        let TypeID::User(name) = type_id else {
+            return type_id.clone();
+        };
+        // Cyclic aliases are rejected by the validation, before anything runs
+        self.module_path
+            .iter()
+            .rev()
+            .copied()
+            .chain(std::iter::once(self.module))
+            .find(|module| module.find_type_alias(name).is_some())
+            .and_then(|module| module.resolve_type(type_id))
+            .unwrap_or_else(|| type_id.clone())
+    }
+
+    /// The name of `type_id` if it is an alias, so type errors can mention it.
+    fn alias_name(&self, type_id: &TypeID) -> Option<String> {
+        match type_id {
+            TypeID::User(name) if self.resolve_type(type_id) != *type_id => Some(name.clone()),
+            _ => None,
+        }
+    }
+
+    fn run_chained_comparison(
+        &mut self,
+        first: &Spanned<Expr>,
+        comparisons: &[(Spanned<BinaryOperator>, Spanned<Expr>)],
+    ) -> ALResult<Value> {
+        let mut lhs = self.run_expr(first)?;
+        let mut holds = true;
+        for (op, operand) in comparisons {
+            let rhs = self.run_expr(operand)?;
+            // Comparisons always result in a bool
+            holds &= run_binary_operator(&op.value, &lhs, &rhs)?
+                .value
+                .as_bool()
+                .unwrap();
+            lhs = rhs;
+        }
+        Ok(Spanned::new(
+            Value::new_bool(holds),
+            first.span.union(&lhs.span),
+        ))
+    }
+
+    fn run_struct_literal(
+        &mut self,
+        span: SourceSpan,
+        name: &Spanned<String>,
+        field_inits: &[(Spanned<String>, Spanned<Expr>)],
+    ) -> ALResult<Value> {
+        let Spanned::<TypeDef> { value, .. } =
+            self.find_type_def(&name.clone().map_value(TypeID::User))?;
+
+        let TypeDef::Struct(struct_def) = value else {
+            return Err(miette!(
+                labels = vec![LabeledSpan::at(name.span, "here")],
+                "Type is not a struct",
+            ));
+        };
+
+        // Report all missing and unknown fields at once, before anything runs
+        let missing = struct_def
+            .fields
+            .iter()
+            .filter(|f| !field_inits.iter().any(|init| init.0.value == f.value.0))
+            .map(|f| f.value.0.clone())
+            .collect::<Vec<_>>();
+        let unknown = field_inits
+            .iter()
+            .filter(|init| !struct_def.fields.iter().any(|f| f.value.0 == init.0.value))
+            .map(|init| &init.0)
+            .collect::<Vec<_>>();
+        if !missing.is_empty() || !unknown.is_empty() {
+            let labels = missing
+                .iter()
+                .map(|field| LabeledSpan::at(name.span, format!("missing field '{}'", field)))
+                .chain(
+                    unknown
+                        .iter()
+                        .map(|field| LabeledSpan::at(field.span, "unknown field")),
+                )
+                .collect();
+            return Err(InvalidStructLiteral {
+                name: name.value.clone(),
+                missing,
+                unknown: unknown.iter().map(|field| field.value.clone()).collect(),
+                labels,
+            }
+            .into());
+        }
+
+        let mut struct_value = StructValue::default();
+        for struct_def_field in struct_def.fields.iter() {
+            let init = field_inits
+                .iter()
+                .find(|f| f.0.value == struct_def_field.value.0)
+                .expect("Missing fields were reported above");
+            let field = self.run_expr(&init.1)?;
+
+            // Handle invalid type
+            let field_type = self.resolve_type(&struct_def_field.value.1);
+            if field.value.type_id() != field_type {
+                return Err(TypeMismatch {
+                    found: field.value.type_id(),
+                    expected: field_type,
+                    reason: TypeMismatchReason::FunctionArgument,
+                    expected_alias: self.alias_name(&struct_def_field.value.1),
+                    declared_span: None,
+                    span: field.span,
+                })
+                .wrap_err("Field initialization");
+            }
+            struct_value.push_field(struct_def_field.value.0.clone(), field);
+        }
+
+        Ok(Spanned::new(
+            Value::new_struct(name.value.clone(), struct_value),
+            span,
+        ))
+    }
+
+    fn run_cast(&self, value: Spanned<Value>, target: &Spanned<TypeID>) -> ALResult<Value> {
+        let target_type = self.resolve_type(&target.value);
+        match value.value.cast(&target_type) {
+            Some(cast) => Ok(Spanned::new(cast, value.span)),
+            None => Err(miette!(
+                labels = vec![
+                    LabeledSpan::at(value.span, "this"),
+                    LabeledSpan::at(target.span, "target type")
+                ],
+                "Can't cast '{}' to '{}'",
+                value.value.type_id(),
+                target_type
+            )),
+        }
+    }
+
     fn find_type_def(&mut self, type_id: &Spanned<TypeID>) -> ALResult<TypeDef> {
-        match &type_id.value {
+        match &self.resolve_type(&type_id.value) {
             TypeID::Int => Ok(TypeDef::PrimitiveInt.into()),
             TypeID::Float => Ok(TypeDef::PrimitiveFloat.into()),
             TypeID::String => Ok(TypeDef::PrimitiveString.into()),
@@ -503,3 +1029,27 @@ impl ExecutionContext<'_> {
         }
     }
 }
+
+/// Applies a binary operator that is not an assignment.
+fn run_binary_operator(
+    op: &BinaryOperator,
+    lhs: &Spanned<Value>,
+    rhs: &Spanned<Value>,
+) -> ALResult<Value> {
+    match op {
+        BinaryOperator::Add => lhs.value.add(rhs),
+        BinaryOperator::Substract => lhs.value.sub(rhs),
+        BinaryOperator::Multiply => lhs.value.mul(rhs),
+        BinaryOperator::Divide => lhs.value.div(rhs),
+        BinaryOperator::And => lhs.value.and(rhs),
+        BinaryOperator::Or => lhs.value.or(rhs),
+        BinaryOperator::Equal => lhs.value.eq(rhs),
+        BinaryOperator::NotEqual => lhs.value.neq(rhs),
+        BinaryOperator::LessThan => lhs.value.lt(rhs),
+        BinaryOperator::LessThanOrEqual => lhs.value.lte(rhs),
+        BinaryOperator::GreaterThan => lhs.value.gt(rhs),
+        BinaryOperator::GreaterThanOrEqual => lhs.value.gte(rhs),
+        // Assignments are handled by `run_assignment`
+        _ => unreachable!(),
+    }
+}
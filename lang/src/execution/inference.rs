@@ -0,0 +1,449 @@
+//! Infers the return type of functions that are declared without `->`.
+
+use miette::Error;
+
+use crate::{
+    error::{TypeMismatch, TypeMismatchReason},
+    module::Module,
+    parser::{
+        binary_expression::{BinaryExpression, BinaryOperator},
+        expression::{DotExpr, Expr},
+        function::FunctionDecl,
+        type_def::{TypeDef, TypeID},
+    },
+    spanned::Spanned,
+    tokenizer::literal::Literal,
+};
+
+use super::ExecutionContext;
+
+/// What is known about the value of an expression before running it.
+#[derive(Debug, Clone, PartialEq)]
+enum Inferred {
+    Type(TypeID),
+    /// Only known while running, e.g. the result of a system function
+    Unknown,
+    /// The expression never produces a value, like `return` or a `loop` without `break`
+    Never,
+}
+
+impl Inferred {
+    /// Combines the possible values of branches. Branches that never produce a value are
+    /// ignored, the others have to agree on a type.
+    fn merge(branches: impl IntoIterator<Item = Inferred>) -> Inferred {
+        branches
+            .into_iter()
+            .filter(|branch| *branch != Inferred::Never)
+            .reduce(|lhs, rhs| if lhs == rhs { lhs } else { Inferred::Unknown })
+            .unwrap_or(Inferred::Never)
+    }
+}
+
+/// The state while inferring the return type of a single function.
+struct FunctionInference {
+    /// Types of the variables, `None` if unknown. Like while running, only the function
+    /// and the body of `for` have their own scope.
+    scopes: Vec<Vec<(String, Option<TypeID>)>>,
+    /// Types of the `return` values with the span of the value
+    returns: Vec<Spanned<TypeID>>,
+    /// Values of `break` for each loop around the current expression, innermost last
+    breaks: Vec<Vec<Inferred>>,
+}
+
+impl FunctionInference {
+    fn variable(&self, name: &str) -> Inferred {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|(variable, _)| variable == name)
+            .and_then(|(_, type_id)| type_id.clone())
+            .map_or(Inferred::Unknown, Inferred::Type)
+    }
+}
+
+impl<'a> ExecutionContext<'a> {
+    /// Infers the return types of the functions and methods without `->` in the nested module
+    /// at the end of `module_path` and in all modules inside of it.
+    pub(super) fn infer_return_types(&mut self, module_path: Vec<&'a Module>) -> Result<(), Error> {
+        let module = module_path.last().copied().unwrap_or(self.module);
+        let methods = module.methods().iter().map(|(_, method)| method);
+        for function in module.functions().iter().chain(methods) {
+            self.return_type(module_path.clone(), function)?;
+        }
+        for (_, nested) in module.modules() {
+            let mut nested_path = module_path.clone();
+            nested_path.push(&nested.value);
+            self.infer_return_types(nested_path)?;
+        }
+        Ok(())
+    }
+
+    /// The declared or inferred return type of `function`, which is declared in the nested
+    /// module at the end of `module_path`. `None` if it is only known while running.
+    pub(super) fn return_type(
+        &mut self,
+        module_path: Vec<&'a Module>,
+        function: &'a Spanned<FunctionDecl>,
+    ) -> Result<Option<Spanned<TypeID>>, Error> {
+        let caller_module_path = std::mem::replace(&mut self.module_path, module_path);
+        let return_type = match &function.value.proto.value.return_type {
+            Some(declared) => Ok(Some(Spanned::new(
+                self.resolve_type(&declared.value),
+                declared.span,
+            ))),
+            None => self.infer_return_type(function),
+        };
+        self.module_path = caller_module_path;
+        return_type
+    }
+
+    /// Every `return` and the value of the body have to agree on one type.
+    /// A body that doesn't return anything has the type `void`.
+    fn infer_return_type(
+        &mut self,
+        function: &'a Spanned<FunctionDecl>,
+    ) -> Result<Option<Spanned<TypeID>>, Error> {
+        let key: *const Spanned<FunctionDecl> = function;
+        if let Some(return_type) = self.inferred_return_types.get(&key) {
+            return Ok(return_type.clone());
+        }
+        // Recursive calls don't tell anything about the type while it is inferred
+        self.inferred_return_types.insert(key, None);
+
+        let arguments = function
+            .value
+            .proto
+            .value
+            .arguments
+            .value
+            .iter()
+            .map(|(name, type_id)| (name.value.clone(), Some(self.resolve_type(&type_id.value))))
+            .collect();
+        let mut inference = FunctionInference {
+            scopes: vec![arguments],
+            returns: Vec::new(),
+            breaks: Vec::new(),
+        };
+
+        let body = &function.value.body;
+        let value = self.infer_expr(body, &mut inference)?;
+        let value_span = match &body.value {
+            Expr::Block(_, Some(tail)) => tail.span,
+            _ => body.span,
+        };
+
+        let mut candidates = inference.returns;
+        match value {
+            Inferred::Type(type_id) => candidates.push(Spanned::new(type_id, value_span)),
+            // Without a `return` the function never comes back, so nothing can go wrong
+            Inferred::Never if candidates.is_empty() => {
+                candidates.push(Spanned::new(TypeID::Void, value_span))
+            }
+            _ => {}
+        }
+
+        let mut candidates = candidates.into_iter();
+        let return_type = candidates.next();
+        if let Some(expected) = &return_type {
+            if let Some(conflict) = candidates.find(|c| c.value != expected.value) {
+                return Err(TypeMismatch {
+                    found: conflict.value,
+                    expected: expected.value.clone(),
+                    reason: TypeMismatchReason::FunctionReturn,
+                    expected_alias: None,
+                    declared_span: Some(expected.span),
+                    span: conflict.span,
+                }
+                .into());
+            }
+        }
+
+        self.inferred_return_types.insert(key, return_type.clone());
+        Ok(return_type)
+    }
+
+    fn infer_expr(
+        &mut self,
+        expr: &'a Spanned<Expr>,
+        inference: &mut FunctionInference,
+    ) -> Result<Inferred, Error> {
+        let inferred = match &expr.value {
+            Expr::Literal(literal) => Inferred::Type(match literal.value {
+                Literal::NumberInt(_) => TypeID::Int,
+                Literal::NumberFloat(_) => TypeID::Float,
+                Literal::String(_) => TypeID::String,
+                Literal::Bool(_) => TypeID::Bool,
+            }),
+            Expr::Variable(name) => inference.variable(&name.value),
+            Expr::StructLiteral(name, fields) => {
+                let values = fields.iter().map(|(_, value)| value);
+                match self.infer_each(values, inference)? {
+                    true => Inferred::Type(TypeID::User(name.value.clone())),
+                    false => Inferred::Never,
+                }
+            }
+            Expr::FunctionCall(name, args) => match self.infer_each(args, inference)? {
+                true => self.infer_call(&name.value)?,
+                false => Inferred::Never,
+            },
+            Expr::Dot { lhs, rhs } => {
+                let receiver = self.infer_expr(lhs, inference)?;
+                match (&rhs.value, receiver) {
+                    (_, Inferred::Never) => Inferred::Never,
+                    (DotExpr::Variable(field), Inferred::Type(type_id)) => {
+                        self.infer_field(&type_id, &field.value)
+                    }
+                    (DotExpr::Variable(_), receiver) => receiver,
+                    (DotExpr::FunctionCall(name, args), receiver) => {
+                        if !self.infer_each(args, inference)? {
+                            Inferred::Never
+                        } else if let Inferred::Type(TypeID::User(type_name)) = receiver {
+                            match self.find_method(&type_name, &name.value) {
+                                Some((module_path, method)) => {
+                                    self.return_type(module_path, method)?.into()
+                                }
+                                None => self.infer_call(&name.value)?,
+                            }
+                        } else if receiver == Inferred::Unknown {
+                            // Without the type of the receiver the method is unknown as well
+                            Inferred::Unknown
+                        } else {
+                            self.infer_call(&name.value)?
+                        }
+                    }
+                }
+            }
+            Expr::Binary(binary) => {
+                let BinaryExpression { lhs, op, rhs } = &binary.value;
+                match (&lhs.value, op.value.is_assignment()) {
+                    (Expr::Variable(name), true) => {
+                        let value = self.infer_expr(rhs, inference)?;
+                        self.infer_assignment(&name.value, &op.value, value, inference)
+                    }
+                    _ => {
+                        let lhs = self.infer_expr(lhs, inference)?;
+                        let rhs = self.infer_expr(rhs, inference)?;
+                        infer_binary(&op.value, lhs, rhs)
+                    }
+                }
+            }
+            Expr::ChainedComparison(first, comparisons) => {
+                let operands = std::iter::once(first.as_ref())
+                    .chain(comparisons.iter().map(|(_, operand)| operand));
+                match self.infer_each(operands, inference)? {
+                    true => Inferred::Type(TypeID::Bool),
+                    false => Inferred::Never,
+                }
+            }
+            Expr::Assignment(name, value) => {
+                let value = self.infer_expr(value, inference)?;
+                self.infer_assignment(&name.value, &BinaryOperator::Assign, value, inference)
+            }
+            Expr::Let(name, type_id, value) => {
+                let value = match value {
+                    Some(value) => Some(self.infer_expr(value, inference)?),
+                    None => None,
+                };
+                if value == Some(Inferred::Never) {
+                    Inferred::Never
+                } else {
+                    let type_id = match (type_id, value) {
+                        (Some(type_id), _) => Some(self.resolve_type(&type_id.value)),
+                        (None, Some(Inferred::Type(type_id))) => Some(type_id),
+                        _ => None,
+                    };
+                    let scope = inference.scopes.last_mut().unwrap();
+                    scope.push((name.value.clone(), type_id));
+                    Inferred::Type(TypeID::Void)
+                }
+            }
+            Expr::IfExpression {
+                if_block: (condition, block),
+                else_if_blocks,
+                else_block,
+            } => {
+                if self.infer_expr(condition, inference)? == Inferred::Never {
+                    Inferred::Never
+                } else {
+                    let mut branches = vec![self.infer_expr(block, inference)?];
+                    for (condition, block) in else_if_blocks {
+                        let branch = match self.infer_expr(condition, inference)? {
+                            Inferred::Never => Inferred::Never,
+                            _ => self.infer_expr(block, inference)?,
+                        };
+                        branches.push(branch);
+                    }
+                    branches.push(match else_block {
+                        Some(block) => self.infer_expr(block, inference)?,
+                        None => Inferred::Type(TypeID::Void),
+                    });
+                    Inferred::merge(branches)
+                }
+            }
+            Expr::Loop(body) => {
+                inference.breaks.push(Vec::new());
+                self.infer_expr(body, inference)?;
+                // Without a `break` the loop can only be left with `return`
+                Inferred::merge(inference.breaks.pop().unwrap())
+            }
+            Expr::For {
+                var,
+                iterable,
+                body,
+            } => {
+                if self.infer_expr(iterable, inference)? == Inferred::Never {
+                    Inferred::Never
+                } else {
+                    inference
+                        .scopes
+                        .push(vec![(var.value.clone(), Some(TypeID::String))]);
+                    inference.breaks.push(Vec::new());
+                    self.infer_expr(body, inference)?;
+                    inference.breaks.pop();
+                    inference.scopes.pop();
+                    Inferred::Type(TypeID::Void)
+                }
+            }
+            Expr::Index { expr, index } => {
+                let operands = [expr.as_ref(), index.as_ref()];
+                match self.infer_each(operands, inference)? {
+                    true => Inferred::Type(TypeID::String),
+                    false => Inferred::Never,
+                }
+            }
+            Expr::Cast { expr, target } => match self.infer_expr(expr, inference)? {
+                Inferred::Never => Inferred::Never,
+                _ => Inferred::Type(self.resolve_type(&target.value)),
+            },
+            Expr::Block(statements, value) => {
+                let finishes = self.infer_each(statements, inference)?;
+                let value = match value {
+                    Some(value) => self.infer_expr(value, inference)?,
+                    None => Inferred::Type(TypeID::Void),
+                };
+                if finishes {
+                    value
+                } else {
+                    Inferred::Never
+                }
+            }
+            Expr::Return(value) => {
+                let span = value.as_ref().map_or(expr.span, |value| value.span);
+                let value = match value {
+                    Some(value) => self.infer_expr(value, inference)?,
+                    None => Inferred::Type(TypeID::Void),
+                };
+                if let Inferred::Type(type_id) = value {
+                    inference.returns.push(Spanned::new(type_id, span));
+                }
+                Inferred::Never
+            }
+            Expr::Break(value) => {
+                let value = match value {
+                    Some(value) => self.infer_expr(value, inference)?,
+                    None => Inferred::Type(TypeID::Void),
+                };
+                // `break` outside of a loop is rejected by the validation
+                if let Some(breaks) = inference.breaks.last_mut() {
+                    breaks.push(value);
+                }
+                Inferred::Never
+            }
+            Expr::Continue => Inferred::Never,
+        };
+        Ok(inferred)
+    }
+
+    /// Infers `exprs` one after another, like the arguments of a call.
+    /// Returns `false` if one of them never produces a value.
+    fn infer_each(
+        &mut self,
+        exprs: impl IntoIterator<Item = &'a Spanned<Expr>>,
+        inference: &mut FunctionInference,
+    ) -> Result<bool, Error> {
+        let mut finishes = true;
+        for expr in exprs {
+            finishes &= self.infer_expr(expr, inference)? != Inferred::Never;
+        }
+        Ok(finishes)
+    }
+
+    /// The result of calling `name`. System functions can return anything.
+    fn infer_call(&mut self, name: &str) -> Result<Inferred, Error> {
+        if self
+            .system_functions
+            .iter()
+            .any(|(system, _)| system == name)
+        {
+            return Ok(Inferred::Unknown);
+        }
+        match self.find_function(name) {
+            Some((module_path, function)) => Ok(self.return_type(module_path, function)?.into()),
+            None => Ok(Inferred::Unknown),
+        }
+    }
+
+    fn infer_field(&self, type_id: &TypeID, field: &str) -> Inferred {
+        let TypeID::User(name) = type_id else {
+            return Inferred::Unknown;
+        };
+        match self.public_types.get(name).map(|type_def| &type_def.value) {
+            Some(TypeDef::Struct(struct_def)) => struct_def
+                .fields
+                .iter()
+                .find(|declared| declared.value.0 == field)
+                .map_or(Inferred::Unknown, |declared| {
+                    Inferred::Type(self.resolve_type(&declared.value.1))
+                }),
+            _ => Inferred::Unknown,
+        }
+    }
+
+    /// An assignment evaluates to the new value of the variable.
+    fn infer_assignment(
+        &self,
+        name: &str,
+        op: &BinaryOperator,
+        value: Inferred,
+        inference: &FunctionInference,
+    ) -> Inferred {
+        match (inference.variable(name), value) {
+            (_, Inferred::Never) => Inferred::Never,
+            (Inferred::Type(type_id), _) => Inferred::Type(type_id),
+            (_, value) if op.compound_operator().is_none() => value,
+            _ => Inferred::Unknown,
+        }
+    }
+}
+
+impl From<Option<Spanned<TypeID>>> for Inferred {
+    fn from(value: Option<Spanned<TypeID>>) -> Self {
+        value.map_or(Inferred::Unknown, |type_id| Inferred::Type(type_id.value))
+    }
+}
+
+fn infer_binary(op: &BinaryOperator, lhs: Inferred, rhs: Inferred) -> Inferred {
+    match (lhs, rhs) {
+        (Inferred::Never, _) | (_, Inferred::Never) => Inferred::Never,
+        _ if op.is_relational()
+            || matches!(
+                op,
+                BinaryOperator::Equal
+                    | BinaryOperator::NotEqual
+                    | BinaryOperator::And
+                    | BinaryOperator::Or
+            ) =>
+        {
+            Inferred::Type(TypeID::Bool)
+        }
+        // Strings concatenate with every primitive
+        (Inferred::Type(TypeID::String), _) | (_, Inferred::Type(TypeID::String))
+            if matches!(op, BinaryOperator::Add) =>
+        {
+            Inferred::Type(TypeID::String)
+        }
+        (Inferred::Type(lhs), _) => Inferred::Type(lhs),
+        (Inferred::Unknown, rhs) => rhs,
+    }
+}
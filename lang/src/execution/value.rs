@@ -1,7 +1,4 @@
-use std::{
-    any::Any,
-    fmt::{Debug, Display},
-};
+use std::{fmt::Display, sync::Arc};
 
 use miette::{miette, Context, LabeledSpan};
 
@@ -12,112 +9,174 @@ use crate::{
     ALResult,
 };
 
-pub struct Value {
-    pub value: Box<dyn Any + Send + Sync>,
-    pub type_id: TypeID,
+#[derive(Debug, Clone)]
+pub enum Value {
+    Void,
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    /// A user defined struct with its type name
+    Struct(String, StructValue),
+    /// A Rust value of a type registered with [`ExecutionContext::register_type`]
+    ///
+    /// [`ExecutionContext::register_type`]: crate::execution::ExecutionContext::register_type
+    Opaque(String, OpaqueValue),
+}
+
+/// Wraps a Rust value, so it can be passed around by the program.
+/// Clones share the wrapped value.
+#[derive(Clone)]
+pub struct OpaqueValue(Arc<dyn std::any::Any + Send + Sync>);
+
+impl std::fmt::Debug for OpaqueValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OpaqueValue")
+    }
 }
 
 impl Value {
     pub fn new_void() -> Self {
-        Self {
-            value: Box::new(()),
-            type_id: TypeID::Void,
-        }
+        Self::Void
     }
 
     pub fn new_int(value: i64) -> Self {
-        Self {
-            value: Box::new(value),
-            type_id: TypeID::Int,
-        }
+        Self::Int(value)
     }
 
     pub fn new_float(value: f64) -> Self {
-        Self {
-            value: Box::new(value),
-            type_id: TypeID::Float,
-        }
+        Self::Float(value)
     }
 
     pub fn new_bool(value: bool) -> Self {
-        Self {
-            value: Box::new(value),
-            type_id: TypeID::Bool,
-        }
+        Self::Bool(value)
     }
 
     pub fn new_string(value: String) -> Self {
-        Self {
-            value: Box::new(value),
-            type_id: TypeID::String,
-        }
+        Self::String(value)
     }
 
     pub fn new_struct(name: String, value: StructValue) -> Self {
-        Self {
-            value: Box::new(value),
-            type_id: TypeID::User(name),
+        Self::Struct(name, value)
+    }
+
+    pub fn new_opaque<T: std::any::Any + Send + Sync>(
+        type_name: impl Into<String>,
+        value: T,
+    ) -> Self {
+        Self::Opaque(type_name.into(), OpaqueValue(Arc::new(value)))
+    }
+
+    pub fn type_id(&self) -> TypeID {
+        match self {
+            Self::Void => TypeID::Void,
+            Self::Int(_) => TypeID::Int,
+            Self::Float(_) => TypeID::Float,
+            Self::Bool(_) => TypeID::Bool,
+            Self::String(_) => TypeID::String,
+            Self::Struct(name, _) | Self::Opaque(name, _) => TypeID::User(name.clone()),
         }
     }
 
+    /// The name of the type as it is written in source, e.g. `int` or the name of a struct.
+    pub fn type_name(&self) -> String {
+        self.type_id().to_string()
+    }
+
     pub fn as_int(&self) -> Option<i64> {
-        if self.type_id == TypeID::Int {
-            self.value.downcast_ref::<i64>().cloned()
-        } else {
-            None
+        match self {
+            Self::Int(value) => Some(*value),
+            _ => None,
         }
     }
 
     pub fn as_float(&self) -> Option<f64> {
-        if self.type_id == TypeID::Float {
-            self.value.downcast_ref::<f64>().cloned()
-        } else {
-            None
+        match self {
+            Self::Float(value) => Some(*value),
+            _ => None,
         }
     }
 
     pub fn as_bool(&self) -> Option<bool> {
-        if self.type_id == TypeID::Bool {
-            self.value.downcast_ref::<bool>().cloned()
-        } else {
-            None
+        match self {
+            Self::Bool(value) => Some(*value),
+            _ => None,
         }
     }
 
     pub fn as_string(&self) -> Option<&str> {
-        if self.type_id == TypeID::String {
-            self.value.downcast_ref::<String>().map(|s| s.as_str())
-        } else {
-            None
+        match self {
+            Self::String(value) => Some(value.as_str()),
+            _ => None,
         }
     }
 
     pub fn as_struct(&self) -> Option<&StructValue> {
-        if matches!(self.type_id, TypeID::User(_)) {
-            self.value.downcast_ref::<StructValue>()
-        } else {
-            None
+        match self {
+            Self::Struct(_, value) => Some(value),
+            _ => None,
         }
     }
 
-    pub fn set_value(&mut self, other: &Spanned<Self>) -> ALResult<()> {
-        if self.type_id == other.value.type_id {
-            match self.type_id {
-                TypeID::Int => self.value = Box::new(other.value.as_int().unwrap()),
-                TypeID::Float => self.value = Box::new(other.value.as_float().unwrap()),
-                TypeID::String => {
-                    self.value = Box::new(other.value.as_string().unwrap().to_string())
-                }
-                TypeID::Bool => self.value = Box::new(other.value.as_bool().unwrap()),
-                TypeID::Void => {}
-                TypeID::User(_) => todo!("Assign user defined values"),
+    pub fn as_struct_mut(&mut self) -> Option<&mut StructValue> {
+        match self {
+            Self::Struct(_, value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the wrapped Rust value if this is an opaque value of type `T`.
+    pub fn as_opaque<T: std::any::Any>(&self) -> Option<&T> {
+        match self {
+            Self::Opaque(_, value) => value.0.downcast_ref(),
+            _ => None,
+        }
+    }
+
+    /// Converts the value to `target`. Floats are truncated toward zero when cast to `int`.
+    /// Returns `None` if there is no conversion between the types.
+    pub fn cast(&self, target: &TypeID) -> Option<Self> {
+        match (self, target) {
+            (Self::Int(value), TypeID::Float) => Some(Self::new_float(*value as f64)),
+            (Self::Float(value), TypeID::Int) => Some(Self::new_int(*value as i64)),
+            (Self::Int(_) | Self::Float(_) | Self::Bool(_), TypeID::String) => {
+                Some(Self::new_string(self.to_string()))
             }
+            _ if self.type_id() == *target => Some(self.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns true if both values are of the same type.
+    /// Compares the variants first, so struct names are only compared for two structs.
+    fn same_type(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Struct(lhs, _), Self::Struct(rhs, _))
+            | (Self::Opaque(lhs, _), Self::Opaque(rhs, _)) => lhs == rhs,
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+
+    /// The error for an operator that the type of both values does not support.
+    fn invalid_operator(&self, other: &Spanned<Self>) -> miette::Error {
+        miette!(
+            labels = vec![LabeledSpan::at(other.span, "here")],
+            "Invalid operator for values of type '{}'",
+            self.type_id()
+        )
+    }
+
+    pub fn set_value(&mut self, other: &Spanned<Self>) -> ALResult<()> {
+        if self.same_type(&other.value) {
+            *self = other.value.clone();
             Ok(Spanned::new((), other.span))
         } else {
             Err(TypeMismatch {
-                found: other.value.type_id.clone(),
-                expected: self.type_id.clone(),
+                found: other.value.type_id(),
+                expected: self.type_id(),
                 reason: TypeMismatchReason::VariableAssignment,
+                expected_alias: None,
+                declared_span: None,
                 span: other.span,
             })
             .wrap_err("Setting value")
@@ -125,61 +184,36 @@ impl Value {
     }
 
     pub fn add(&self, other: &Spanned<Self>) -> ALResult<Self> {
-        /*if self.type_id != other.value.type_id {
-            return Err(Error::new_type_mismatch(
-                other.span,
-                self.type_id.clone(),
-                other.value.type_id.clone(),
-                TypeMismatchReason::BinaryOperation(BinaryOperator::Add),
-            ));
-        }*/
-
-        match (&self.type_id, &other.value.type_id) {
-            (TypeID::Int, TypeID::Int) => Ok(Self::new_int(
-                self.as_int().unwrap() + other.value.as_int().unwrap(),
-            )),
+        match (self, &other.value) {
+            (Self::Int(lhs), Self::Int(rhs)) => Ok(Self::new_int(lhs + rhs)),
             // Enable for implicit casting
-            // (TypeID::Int, TypeID::Float) => Ok(Self::new_float(
-            //     self.as_int().unwrap() as f64 + other.value.as_float().unwrap(),
-            // )),
-            (TypeID::Float, TypeID::Float) => Ok(Self::new_float(
-                self.as_float().unwrap() + other.value.as_float().unwrap(),
-            )),
+            // (Self::Int(lhs), Self::Float(rhs)) => Ok(Self::new_float(*lhs as f64 + rhs)),
+            (Self::Float(lhs), Self::Float(rhs)) => Ok(Self::new_float(lhs + rhs)),
             // Enable for implicit casting
-            // (TypeID::Float, TypeID::Int) => Ok(Self::new_float(
-            //     self.as_float().unwrap() + other.value.as_int().unwrap() as f64,
-            // )),
-            (TypeID::String, TypeID::String) => {
-                let mut s = self.as_string().unwrap().to_string();
-                s.push_str(other.value.as_string().unwrap());
-                Ok(Self::new_string(s))
+            // (Self::Float(lhs), Self::Int(rhs)) => Ok(Self::new_float(lhs + *rhs as f64)),
+            // Strings concatenate with every primitive, no matter on which side the string is.
+            // Both sides are formatted with `Display`.
+            (Self::String(_), Self::String(_) | Self::Int(_) | Self::Float(_) | Self::Bool(_))
+            | (Self::Int(_) | Self::Float(_) | Self::Bool(_), Self::String(_)) => {
+                Ok(Self::new_string(format!("{}{}", self, other.value)))
             }
-            (TypeID::String, TypeID::Int)
-            | (TypeID::String, TypeID::Float)
-            | (TypeID::String, TypeID::Bool) => Ok(Self::new_string(format!(
-                "{}{}",
-                self.as_string().unwrap(),
-                other.value
-            ))),
-            (TypeID::Int, TypeID::String)
-            | (TypeID::Float, TypeID::String)
-            | (TypeID::Bool, TypeID::String) => Ok(Self::new_string(format!(
-                "{}{}",
-                self,
-                other.value.as_string().unwrap()
-            ))),
-            (TypeID::Bool, _) | (_, TypeID::Bool) => Err(miette!(
+            (Self::Bool(_), _) | (_, Self::Bool(_)) => Err(miette!(
                 labels = vec![LabeledSpan::at(other.span, "here")],
                 "Invalid operator for boolean values"
             )),
-            (TypeID::Void, _) => Ok(Value::new_void()),
-            (_, TypeID::Void) => Ok(self.clone()),
-            (TypeID::User(_), _) => todo!(),
-            (_, TypeID::User(_)) => todo!(),
+            (Self::Void, _) => Ok(Value::new_void()),
+            (_, Self::Void) => Ok(self.clone()),
+            (Self::Struct(..), Self::Struct(..)) | (Self::Opaque(..), Self::Opaque(..))
+                if self.same_type(&other.value) =>
+            {
+                Err(self.invalid_operator(other))
+            }
             (_, _) => Err(TypeMismatch {
-                found: other.value.type_id.clone(),
-                expected: self.type_id.clone(),
+                found: other.value.type_id(),
+                expected: self.type_id(),
                 reason: TypeMismatchReason::BinaryOperation(BinaryOperator::Add),
+                expected_alias: None,
+                declared_span: None,
                 span: other.span,
             })
             .wrap_err("Adding values"),
@@ -188,125 +222,113 @@ impl Value {
     }
 
     pub fn sub(&self, other: &Spanned<Self>) -> ALResult<Self> {
-        if self.type_id != other.value.type_id {
+        if !self.same_type(&other.value) {
             return Err(TypeMismatch {
-                found: other.value.type_id.clone(),
-                expected: self.type_id.clone(),
+                found: other.value.type_id(),
+                expected: self.type_id(),
                 reason: TypeMismatchReason::BinaryOperation(BinaryOperator::Substract),
+                expected_alias: None,
+                declared_span: None,
                 span: other.span,
             })
             .wrap_err("Subtracting values");
         }
 
-        match self.type_id {
-            TypeID::Int => Ok(Self::new_int(
-                self.as_int().unwrap() - other.value.as_int().unwrap(),
-            )),
-            TypeID::Float => Ok(Self::new_float(
-                self.as_float().unwrap() - other.value.as_float().unwrap(),
-            )),
-            TypeID::String => todo!(),
-            TypeID::Bool => Err(miette!(
+        match (self, &other.value) {
+            (Self::Int(lhs), Self::Int(rhs)) => Ok(Self::new_int(lhs - rhs)),
+            (Self::Float(lhs), Self::Float(rhs)) => Ok(Self::new_float(lhs - rhs)),
+            (Self::Bool(_), _) => Err(miette!(
                 labels = vec![LabeledSpan::at(other.span, "here")],
                 "Invalid operator for boolean values"
             )),
-            TypeID::Void => todo!(),
-            TypeID::User(_) => todo!(),
+            _ => Err(self.invalid_operator(other)),
         }
         .map(|v| Spanned::new(v, other.span))
     }
 
     pub fn mul(&self, other: &Spanned<Self>) -> ALResult<Self> {
-        if self.type_id != other.value.type_id {
+        if !self.same_type(&other.value) {
             return Err(TypeMismatch {
-                found: other.value.type_id.clone(),
-                expected: self.type_id.clone(),
+                found: other.value.type_id(),
+                expected: self.type_id(),
                 reason: TypeMismatchReason::BinaryOperation(BinaryOperator::Multiply),
+                expected_alias: None,
+                declared_span: None,
                 span: other.span,
             })
             .wrap_err("Multiplying values");
         }
 
-        match self.type_id {
-            TypeID::Int => Ok(Self::new_int(
-                self.as_int().unwrap() * other.value.as_int().unwrap(),
-            )),
-            TypeID::Float => Ok(Self::new_float(
-                self.as_float().unwrap() * other.value.as_float().unwrap(),
-            )),
-            TypeID::String => todo!(),
-            TypeID::Bool => Err(miette!(
+        match (self, &other.value) {
+            (Self::Int(lhs), Self::Int(rhs)) => Ok(Self::new_int(lhs * rhs)),
+            (Self::Float(lhs), Self::Float(rhs)) => Ok(Self::new_float(lhs * rhs)),
+            (Self::Bool(_), _) => Err(miette!(
                 labels = vec![LabeledSpan::at(other.span, "here")],
                 "Invalid operator for boolean values"
             )),
-            TypeID::Void => todo!(),
-            TypeID::User(_) => todo!(),
+            _ => Err(self.invalid_operator(other)),
         }
         .map(|v| Spanned::new(v, other.span))
     }
 
     pub fn div(&self, other: &Spanned<Self>) -> ALResult<Self> {
-        if self.type_id != other.value.type_id {
+        if !self.same_type(&other.value) {
             return Err(TypeMismatch {
-                found: other.value.type_id.clone(),
-                expected: self.type_id.clone(),
+                found: other.value.type_id(),
+                expected: self.type_id(),
                 reason: TypeMismatchReason::BinaryOperation(BinaryOperator::Divide),
+                expected_alias: None,
+                declared_span: None,
                 span: other.span,
             })
             .wrap_err("Dividing values");
         }
 
-        match self.type_id {
-            TypeID::Int => Ok(Self::new_int(
-                self.as_int().unwrap() / other.value.as_int().unwrap(),
-            )),
-            TypeID::Float => Ok(Self::new_float(
-                self.as_float().unwrap() / other.value.as_float().unwrap(),
-            )),
-            TypeID::String => todo!(),
-            TypeID::Bool => Err(miette!(
+        match (self, &other.value) {
+            (Self::Int(lhs), Self::Int(rhs)) => Ok(Self::new_int(lhs / rhs)),
+            (Self::Float(lhs), Self::Float(rhs)) => Ok(Self::new_float(lhs / rhs)),
+            (Self::Bool(_), _) => Err(miette!(
                 labels = vec![LabeledSpan::at(other.span, "here")],
                 "Invalid operator for boolean values"
             )),
-            TypeID::Void => todo!(),
-            TypeID::User(_) => todo!(),
+            _ => Err(self.invalid_operator(other)),
         }
         .map(|v| Spanned::new(v, other.span))
     }
 
     // Logical operations
     pub fn and(&self, other: &Spanned<Self>) -> ALResult<Self> {
-        if self.type_id != TypeID::Bool || other.value.type_id != TypeID::Bool {
-            return Err(TypeMismatch {
-                found: other.value.type_id.clone(),
-                expected: self.type_id.clone(),
+        match (self, &other.value) {
+            (Self::Bool(lhs), Self::Bool(rhs)) => {
+                Ok(Spanned::new(Self::new_bool(*lhs && *rhs), other.span))
+            }
+            _ => Err(TypeMismatch {
+                found: other.value.type_id(),
+                expected: self.type_id(),
                 reason: TypeMismatchReason::BinaryOperation(BinaryOperator::And),
+                expected_alias: None,
+                declared_span: None,
                 span: other.span,
             })
-            .wrap_err("And operation");
+            .wrap_err("And operation"),
         }
-
-        Ok(Self::new_bool(
-            self.as_bool().unwrap() && other.value.as_bool().unwrap(),
-        ))
-        .map(|v| Spanned::new(v, other.span))
     }
 
     pub fn or(&self, other: &Spanned<Self>) -> ALResult<Self> {
-        if self.type_id != TypeID::Bool || other.value.type_id != TypeID::Bool {
-            return Err(TypeMismatch {
-                found: other.value.type_id.clone(),
-                expected: self.type_id.clone(),
+        match (self, &other.value) {
+            (Self::Bool(lhs), Self::Bool(rhs)) => {
+                Ok(Spanned::new(Self::new_bool(*lhs || *rhs), other.span))
+            }
+            _ => Err(TypeMismatch {
+                found: other.value.type_id(),
+                expected: self.type_id(),
                 reason: TypeMismatchReason::BinaryOperation(BinaryOperator::Or),
+                expected_alias: None,
+                declared_span: None,
                 span: other.span,
             })
-            .wrap_err("Or operation");
+            .wrap_err("Or operation"),
         }
-
-        Ok(Self::new_bool(
-            self.as_bool().unwrap() || other.value.as_bool().unwrap(),
-        ))
-        .map(|v| Spanned::new(v, other.span))
     }
 
     // Comparison operations
@@ -314,31 +336,39 @@ impl Value {
     /// ### NOTE
     /// This will always return a boolean value or an error if the types dont match.
     pub fn eq(&self, other: &Spanned<Self>) -> ALResult<Self> {
-        if self.type_id != other.value.type_id {
+        if !self.same_type(&other.value) {
             return Err(TypeMismatch {
-                found: other.value.type_id.clone(),
-                expected: self.type_id.clone(),
+                found: other.value.type_id(),
+                expected: self.type_id(),
                 reason: TypeMismatchReason::BinaryOperation(BinaryOperator::Equal),
+                expected_alias: None,
+                declared_span: None,
                 span: other.span,
             })
             .wrap_err("Equal operation");
         }
 
-        match self.type_id {
-            TypeID::Int => Ok(Self::new_bool(
-                self.as_int().unwrap() == other.value.as_int().unwrap(),
-            )),
-            TypeID::Float => Ok(Self::new_bool(
-                self.as_float().unwrap() == other.value.as_float().unwrap(),
-            )),
-            TypeID::String => Ok(Self::new_bool(
-                self.as_string().unwrap() == other.value.as_string().unwrap(),
-            )),
-            TypeID::Bool => Ok(Self::new_bool(
-                self.as_bool().unwrap() == other.value.as_bool().unwrap(),
-            )),
-            TypeID::Void => Ok(Self::new_bool(true)),
-            TypeID::User(_) => todo!(),
+        match (self, &other.value) {
+            (Self::Int(lhs), Self::Int(rhs)) => Ok(Self::new_bool(lhs == rhs)),
+            (Self::Float(lhs), Self::Float(rhs)) => Ok(Self::new_bool(lhs == rhs)),
+            (Self::String(lhs), Self::String(rhs)) => Ok(Self::new_bool(lhs == rhs)),
+            (Self::Bool(lhs), Self::Bool(rhs)) => Ok(Self::new_bool(lhs == rhs)),
+            (Self::Struct(_, lhs), Self::Struct(_, rhs)) => {
+                // Same type, so both have the same fields in the same order
+                let mut equal = true;
+                for ((_, lhs), (_, rhs)) in lhs.fields().zip(rhs.fields()) {
+                    if !lhs.value.eq(rhs)?.value.as_bool().unwrap() {
+                        equal = false;
+                        break;
+                    }
+                }
+                Ok(Self::new_bool(equal))
+            }
+            // Opaque values are only equal to themselves and their clones
+            (Self::Opaque(_, lhs), Self::Opaque(_, rhs)) => {
+                Ok(Self::new_bool(Arc::ptr_eq(&lhs.0, &rhs.0)))
+            }
+            _ => Ok(Self::new_bool(true)),
         }
         .map(|v| Spanned::new(v, other.span))
     }
@@ -351,63 +381,55 @@ impl Value {
     }
 
     pub fn lt(&self, other: &Spanned<Self>) -> ALResult<Self> {
-        if self.type_id != other.value.type_id {
+        if !self.same_type(&other.value) {
             return Err(TypeMismatch {
-                found: other.value.type_id.clone(),
-                expected: self.type_id.clone(),
+                found: other.value.type_id(),
+                expected: self.type_id(),
                 reason: TypeMismatchReason::BinaryOperation(BinaryOperator::LessThan),
+                expected_alias: None,
+                declared_span: None,
                 span: other.span,
             })
             .wrap_err("Less than operation");
         }
 
-        match self.type_id {
-            TypeID::Int => Ok(Self::new_bool(
-                self.as_int().unwrap() < other.value.as_int().unwrap(),
-            )),
-            TypeID::Float => Ok(Self::new_bool(
-                self.as_float().unwrap() < other.value.as_float().unwrap(),
-            )),
-            TypeID::String => Ok(Self::new_bool(
-                self.as_string().unwrap() < other.value.as_string().unwrap(),
-            )),
-            TypeID::Bool => Err(miette!(
+        match (self, &other.value) {
+            (Self::Int(lhs), Self::Int(rhs)) => Ok(Self::new_bool(lhs < rhs)),
+            (Self::Float(lhs), Self::Float(rhs)) => Ok(Self::new_bool(lhs < rhs)),
+            (Self::String(lhs), Self::String(rhs)) => Ok(Self::new_bool(lhs < rhs)),
+            (Self::Bool(_), _) => Err(miette!(
                 labels = vec![LabeledSpan::at(other.span, "here")],
                 "Invalid operator for boolean values"
             )),
-            TypeID::Void => Ok(Self::new_bool(true)),
-            TypeID::User(_) => todo!(),
+            (Self::Void, _) => Ok(Self::new_bool(true)),
+            _ => Err(self.invalid_operator(other)),
         }
         .map(|v| Spanned::new(v, other.span))
     }
 
     pub fn gt(&self, other: &Spanned<Self>) -> ALResult<Self> {
-        if self.type_id != other.value.type_id {
+        if !self.same_type(&other.value) {
             return Err(TypeMismatch {
-                found: other.value.type_id.clone(),
-                expected: self.type_id.clone(),
+                found: other.value.type_id(),
+                expected: self.type_id(),
                 reason: TypeMismatchReason::BinaryOperation(BinaryOperator::GreaterThan),
+                expected_alias: None,
+                declared_span: None,
                 span: other.span,
             })
             .wrap_err("Greater than operation");
         }
 
-        match self.type_id {
-            TypeID::Int => Ok(Self::new_bool(
-                self.as_int().unwrap() > other.value.as_int().unwrap(),
-            )),
-            TypeID::Float => Ok(Self::new_bool(
-                self.as_float().unwrap() > other.value.as_float().unwrap(),
-            )),
-            TypeID::String => Ok(Self::new_bool(
-                self.as_string().unwrap() > other.value.as_string().unwrap(),
-            )),
-            TypeID::Bool => Err(miette!(
+        match (self, &other.value) {
+            (Self::Int(lhs), Self::Int(rhs)) => Ok(Self::new_bool(lhs > rhs)),
+            (Self::Float(lhs), Self::Float(rhs)) => Ok(Self::new_bool(lhs > rhs)),
+            (Self::String(lhs), Self::String(rhs)) => Ok(Self::new_bool(lhs > rhs)),
+            (Self::Bool(_), _) => Err(miette!(
                 labels = vec![LabeledSpan::at(other.span, "here")],
                 "Invalid operator for boolean values"
             )),
-            TypeID::Void => Ok(Self::new_bool(true)),
-            TypeID::User(_) => todo!(),
+            (Self::Void, _) => Ok(Self::new_bool(true)),
+            _ => Err(self.invalid_operator(other)),
         }
         .map(|v| Spanned::new(v, other.span))
     }
@@ -442,41 +464,27 @@ impl Value {
     }
 }
 
-impl Clone for Value {
-    fn clone(&self) -> Self {
-        match &self.type_id {
-            TypeID::Int => Self::new_int(self.as_int().unwrap()),
-            TypeID::Float => Self::new_float(self.as_float().unwrap()),
-            TypeID::String => Self::new_string(self.as_string().unwrap().to_string()),
-            TypeID::Bool => Self::new_bool(self.as_bool().unwrap()),
-            TypeID::Void => Self::new_void(),
-            TypeID::User(name) => Self::new_struct(name.clone(), self.as_struct().unwrap().clone()),
-        }
-    }
-}
+/// The default value of a type. User defined types have none.
+impl TryFrom<TypeID> for Value {
+    type Error = miette::Error;
 
-impl Debug for Value {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?} ({})", self.value, self.type_id)
-    }
-}
-
-impl From<TypeID> for Value {
-    fn from(value: TypeID) -> Self {
+    fn try_from(value: TypeID) -> Result<Self, Self::Error> {
         match value {
-            TypeID::Int => Self::new_int(0),
-            TypeID::Float => Self::new_float(0.0),
-            TypeID::String => Self::new_string(String::new()),
-            TypeID::Bool => Self::new_bool(false),
-            TypeID::Void => Self::new_void(),
-            TypeID::User(_) => todo!(),
+            TypeID::Int => Ok(Self::new_int(0)),
+            TypeID::Float => Ok(Self::new_float(0.0)),
+            TypeID::String => Ok(Self::new_string(String::new())),
+            TypeID::Bool => Ok(Self::new_bool(false)),
+            TypeID::Void => Ok(Self::new_void()),
+            TypeID::User(name) => Err(miette!("Type '{}' has no default value", name)),
         }
     }
 }
 
-impl From<Spanned<TypeID>> for Value {
-    fn from(value: Spanned<TypeID>) -> Self {
-        Self::from(value.value)
+impl TryFrom<Spanned<TypeID>> for Value {
+    type Error = miette::Error;
+
+    fn try_from(value: Spanned<TypeID>) -> Result<Self, Self::Error> {
+        Self::try_from(value.value)
     }
 }
 
@@ -518,13 +526,23 @@ impl From<()> for Value {
 
 impl Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.type_id {
-            TypeID::Int => write!(f, "{}", self.as_int().unwrap()),
-            TypeID::Float => write!(f, "{}", self.as_float().unwrap()),
-            TypeID::String => write!(f, "{}", self.as_string().unwrap()),
-            TypeID::Bool => write!(f, "{}", self.as_bool().unwrap()),
-            TypeID::Void => write!(f, "void"),
-            TypeID::User(_) => todo!(),
+        match self {
+            Self::Int(value) => write!(f, "{}", value),
+            Self::Float(value) => write!(f, "{}", value),
+            Self::String(value) => write!(f, "{}", value),
+            Self::Bool(value) => write!(f, "{}", value),
+            Self::Void => write!(f, "void"),
+            Self::Struct(name, value) => {
+                write!(f, "{} {{ ", name)?;
+                for (i, (field, value)) in value.fields().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", field, value.value)?;
+                }
+                write!(f, " }}")
+            }
+            Self::Opaque(name, _) => write!(f, "<{}>", name),
         }
     }
 }
@@ -1,9 +1,11 @@
+use std::iter::Peekable;
+#[cfg(feature = "native")]
 use std::{
     fs::File,
     io::{BufReader, Seek},
-    iter::Peekable,
 };
 
+#[cfg(feature = "native")]
 use utf8_chars::BufReadCharsExt;
 
 pub trait InputStream {
@@ -72,7 +74,7 @@ where
     }
 }
 
-impl<'a> InputStream for &'a str {
+impl InputStream for &str {
     type Output = char;
 
     fn next(&mut self) -> Option<Self::Output> {
@@ -86,8 +88,8 @@ impl<'a> InputStream for &'a str {
     }
 
     fn advance(&mut self) {
-        if !self.is_empty() {
-            *self = &self[1..];
+        if let Some(c) = self.chars().next() {
+            *self = &self[c.len_utf8()..];
         }
     }
 
@@ -96,10 +98,12 @@ impl<'a> InputStream for &'a str {
     }
 }
 
+#[cfg(feature = "native")]
 pub struct FileInputStream {
     reader: BufReader<File>,
 }
 
+#[cfg(feature = "native")]
 impl FileInputStream {
     pub fn new(file: File) -> Self {
         Self {
@@ -108,6 +112,7 @@ impl FileInputStream {
     }
 }
 
+#[cfg(feature = "native")]
 impl InputStream for FileInputStream {
     type Output = char;
 
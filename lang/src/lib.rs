@@ -9,5 +9,8 @@ pub mod prelude;
 pub mod spanned;
 pub mod system_functions;
 pub mod tokenizer;
+pub mod validation;
+pub mod visit;
+pub mod wasm;
 
 pub type ALResult<T> = Result<Spanned<T>, miette::Error>;
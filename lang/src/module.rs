@@ -1,12 +1,44 @@
+use std::collections::HashMap;
+
+use miette::{miette, Error, LabeledSpan};
+
 use crate::{
-    parser::{function::FunctionDecl, structs::Struct},
+    error::Redefinition,
+    parser::{expression::Expr, function::FunctionDecl, structs::Struct, type_def::TypeID},
     spanned::Spanned,
 };
 
+/// Anything that can be written at the top level of a source file.
+#[derive(Debug)]
+pub enum ModuleItem {
+    Function(Spanned<FunctionDecl>),
+    Struct(Spanned<String>, Spanned<Struct>),
+    TypeAlias(Spanned<String>, Spanned<TypeID>),
+    /// `mod name { ... }`
+    Module(Spanned<String>, Spanned<Module>),
+    /// `impl Type { fn method(self) { ... } }`
+    Impl(Spanned<String>, Vec<Spanned<FunctionDecl>>),
+    /// Only valid in a REPL, modules can't contain expressions
+    Expression(Spanned<Expr>),
+}
+
+#[derive(Debug)]
 pub struct Module {
     name: String,
     functions: Vec<Spanned<FunctionDecl>>,
     structs: Vec<(Spanned<String>, Spanned<Struct>)>,
+    type_aliases: Vec<(Spanned<String>, Spanned<TypeID>)>,
+    modules: Vec<(Spanned<String>, Spanned<Module>)>,
+    /// Functions of `impl` blocks together with the name of their type
+    methods: Vec<(Spanned<String>, Spanned<FunctionDecl>)>,
+
+    // Name to index lookup for the vectors above
+    function_indices: HashMap<String, usize>,
+    struct_indices: HashMap<String, usize>,
+    type_alias_indices: HashMap<String, usize>,
+    module_indices: HashMap<String, usize>,
+    /// Type name and method name to index
+    method_indices: HashMap<(String, String), usize>,
 }
 
 impl Module {
@@ -15,6 +47,14 @@ impl Module {
             name: name.into(),
             functions: Vec::default(),
             structs: Vec::default(),
+            type_aliases: Vec::default(),
+            modules: Vec::default(),
+            methods: Vec::default(),
+            function_indices: HashMap::default(),
+            struct_indices: HashMap::default(),
+            type_alias_indices: HashMap::default(),
+            module_indices: HashMap::default(),
+            method_indices: HashMap::default(),
         }
     }
 
@@ -22,7 +62,99 @@ impl Module {
         &self.name
     }
 
+    /// Adds a declaration to the module.
+    /// # Errors
+    /// Returns [`Redefinition`] if the name is already taken, or an error for expressions.
+    pub fn add_item(&mut self, item: ModuleItem) -> Result<(), Error> {
+        match item {
+            ModuleItem::Function(function) => {
+                let name = &function.value.proto.value.name;
+                if let Some(first) = self.find_function(name) {
+                    return Err(Redefinition::Function {
+                        name: name.value.clone(),
+                        first: first.value.proto.value.name.span,
+                        second: name.span,
+                    }
+                    .into());
+                }
+                self.add_function(function);
+            }
+            ModuleItem::Struct(name, strct) => {
+                if let Some((first, _)) = self.find_struct(&name) {
+                    return Err(Redefinition::Struct {
+                        name: name.value,
+                        first: first.span,
+                        second: name.span,
+                    }
+                    .into());
+                }
+                if let Some((first, _)) = self.find_type_alias(&name) {
+                    return Err(Redefinition::Type {
+                        name: name.value,
+                        first: first.span,
+                        second: name.span,
+                    }
+                    .into());
+                }
+                self.add_struct(name, strct);
+            }
+            ModuleItem::TypeAlias(name, type_id) => {
+                let first = self
+                    .find_type_alias(&name)
+                    .map(|(name, _)| name)
+                    .or(self.find_struct(&name).map(|(name, _)| name));
+                if let Some(first) = first {
+                    return Err(Redefinition::Type {
+                        name: name.value,
+                        first: first.span,
+                        second: name.span,
+                    }
+                    .into());
+                }
+                self.add_type_alias(name, type_id);
+            }
+            ModuleItem::Module(name, module) => {
+                if let Some((first, _)) = self.find_module(&name) {
+                    return Err(Redefinition::Module {
+                        name: name.value,
+                        first: first.span,
+                        second: name.span,
+                    }
+                    .into());
+                }
+                self.add_module(name, module);
+            }
+            ModuleItem::Impl(type_name, methods) => {
+                for method in methods {
+                    let name = &method.value.proto.value.name;
+                    if let Some(first) = self.find_method(&type_name.value, &name.value) {
+                        return Err(Redefinition::Method {
+                            type_name: type_name.value.clone(),
+                            name: name.value.clone(),
+                            first: first.value.proto.value.name.span,
+                            second: name.span,
+                        }
+                        .into());
+                    }
+                    self.add_method(type_name.clone(), method);
+                }
+            }
+            ModuleItem::Expression(expr) => {
+                return Err(miette!(
+                    labels = vec![LabeledSpan::at(expr.span, "here")],
+                    "Expressions can't be added to a module",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a function to the module. If a function with the same name already exists, lookups
+    /// will still find the first one.
     pub fn add_function(&mut self, func: Spanned<FunctionDecl>) {
+        self.function_indices
+            .entry(func.value.proto.value.name.value.clone())
+            .or_insert(self.functions.len());
         self.functions.push(func);
     }
 
@@ -30,11 +162,114 @@ impl Module {
         &self.functions
     }
 
+    pub fn find_function(&self, name: &str) -> Option<&Spanned<FunctionDecl>> {
+        self.function_indices
+            .get(name)
+            .map(|index| &self.functions[*index])
+    }
+
+    /// Adds a struct to the module. If a struct with the same name already exists, lookups
+    /// will still find the first one.
     pub fn add_struct(&mut self, name: Spanned<String>, strct: Spanned<Struct>) {
+        self.struct_indices
+            .entry(name.value.clone())
+            .or_insert(self.structs.len());
         self.structs.push((name, strct));
     }
 
     pub fn structs(&self) -> &[(Spanned<String>, Spanned<Struct>)] {
         &self.structs
     }
+
+    pub fn find_struct(&self, name: &str) -> Option<&(Spanned<String>, Spanned<Struct>)> {
+        self.struct_indices
+            .get(name)
+            .map(|index| &self.structs[*index])
+    }
+
+    /// Adds a type alias to the module. If an alias with the same name already exists, lookups
+    /// will still find the first one.
+    pub fn add_type_alias(&mut self, name: Spanned<String>, type_id: Spanned<TypeID>) {
+        self.type_alias_indices
+            .entry(name.value.clone())
+            .or_insert(self.type_aliases.len());
+        self.type_aliases.push((name, type_id));
+    }
+
+    pub fn type_aliases(&self) -> &[(Spanned<String>, Spanned<TypeID>)] {
+        &self.type_aliases
+    }
+
+    pub fn find_type_alias(&self, name: &str) -> Option<&(Spanned<String>, Spanned<TypeID>)> {
+        self.type_alias_indices
+            .get(name)
+            .map(|index| &self.type_aliases[*index])
+    }
+
+    /// Adds a nested module. If a module with the same name already exists, lookups
+    /// will still find the first one.
+    pub fn add_module(&mut self, name: Spanned<String>, module: Spanned<Module>) {
+        self.module_indices
+            .entry(name.value.clone())
+            .or_insert(self.modules.len());
+        self.modules.push((name, module));
+    }
+
+    pub fn modules(&self) -> &[(Spanned<String>, Spanned<Module>)] {
+        &self.modules
+    }
+
+    pub fn find_module(&self, name: &str) -> Option<&(Spanned<String>, Spanned<Module>)> {
+        self.module_indices
+            .get(name)
+            .map(|index| &self.modules[*index])
+    }
+
+    /// Adds a method of the type `type_name`. If the type already has a method with the same name,
+    /// lookups will still find the first one.
+    pub fn add_method(&mut self, type_name: Spanned<String>, method: Spanned<FunctionDecl>) {
+        self.method_indices
+            .entry((
+                type_name.value.clone(),
+                method.value.proto.value.name.value.clone(),
+            ))
+            .or_insert(self.methods.len());
+        self.methods.push((type_name, method));
+    }
+
+    pub fn methods(&self) -> &[(Spanned<String>, Spanned<FunctionDecl>)] {
+        &self.methods
+    }
+
+    pub fn find_method(&self, type_name: &str, name: &str) -> Option<&Spanned<FunctionDecl>> {
+        self.method_indices
+            .get(&(type_name.to_string(), name.to_string()))
+            .map(|index| &self.methods[*index].1)
+    }
+
+    /// Finds a function by a path like `inner::helper`, relative to this module.
+    /// Returns the function together with the module it is declared in.
+    pub fn resolve_function(&self, path: &str) -> Option<(&Module, &Spanned<FunctionDecl>)> {
+        match path.split_once("::") {
+            Some((module, rest)) => self.find_module(module)?.1.value.resolve_function(rest),
+            None => self.find_function(path).map(|function| (self, function)),
+        }
+    }
+
+    /// Follows type aliases until a type is found that is not an alias.
+    /// Returns `None` if the aliases form a cycle.
+    pub fn resolve_type(&self, type_id: &TypeID) -> Option<TypeID> {
+        let mut type_id = type_id;
+        // Every alias can be visited at most once, otherwise we are in a cycle
+        for _ in 0..=self.type_aliases.len() {
+            match type_id {
+                TypeID::User(name) => match self.find_type_alias(name) {
+                    Some((_, aliased)) => type_id = &aliased.value,
+                    None => return Some(type_id.clone()),
+                },
+                _ => return Some(type_id.clone()),
+            }
+        }
+        None
+    }
 }
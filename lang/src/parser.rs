@@ -7,9 +7,12 @@ use structs::Struct;
 use type_def::TypeID;
 
 use crate::{
-    error::UnexpectedToken,
+    error::{
+        InvalidNumber, MissingSemicolon, MissingSeparator, ReservedKeyword, UnexpectedCharacter,
+        UnexpectedEof, UnexpectedToken,
+    },
     input_stream::InputStream,
-    module::Module,
+    module::{Module, ModuleItem},
     spanned::{SpanExt, Spanned},
     tokenizer::{identifier::Identifier, token::Token, Tokenizer},
     ALResult,
@@ -36,6 +39,11 @@ pub mod type_def;
 pub struct Parser<'a> {
     input: ResetIterator<Tokenizer<'a>>,
     last_offset: usize,
+    /// `///` comments in front of the next token
+    doc_comments: Vec<String>,
+    /// Set while parsing the condition of `if` or the iterable of `for`,
+    /// where `x {}` is the variable `x` followed by a block
+    in_condition: bool,
 }
 
 impl<'a> Parser<'a> {
@@ -48,6 +56,8 @@ impl<'a> Parser<'a> {
         Self {
             input: Tokenizer::new(input).into(),
             last_offset: 0,
+            doc_comments: Vec::new(),
+            in_condition: false,
         }
     }
 }
@@ -75,52 +85,201 @@ impl Parser<'_> {
         let mut module = Module::new("main");
         let mut module_span = SourceSpan::new(SourceOffset::from(0), 0);
 
-        while let Ok(Spanned::<Token> { value, span }) = self.peek() {
-            module_span = module_span.union(&span);
-            match value {
-                Token::Identifier(Identifier::Function) => {
-                    self.consume();
-                    let function = self.parse_function()?;
-                    module.add_function(function);
-                }
-                Token::Identifier(Identifier::Struct) => {
-                    self.consume();
-                    let struct_name = self.parse_user_defined_identifier()?;
-                    let struct_decl = self.parse_struct()?;
-                    module.add_struct(struct_name, struct_decl);
-                }
-                _ => {
-                    return Err(UnexpectedToken {
-                        found: value,
-                        span,
-                        expected: "Expected function or struct".into(),
-                    })
-                    .wrap_err("Parsing module");
-                }
+        loop {
+            // A module can end with doc comments that don't belong to anything
+            self.skip_doc_comments();
+            if self.input.peek().is_none() {
+                break;
             }
+
+            let Spanned::<Token> { value, span } = self.peek()?;
+            if !matches!(
+                value,
+                Token::Identifier(
+                    Identifier::Function
+                        | Identifier::Struct
+                        | Identifier::Type
+                        | Identifier::Mod
+                        | Identifier::Impl
+                )
+            ) {
+                return Err(UnexpectedToken {
+                    found: value,
+                    span,
+                    expected: "Expected function, struct, type, mod or impl".into(),
+                })
+                .wrap_err("Parsing module");
+            }
+
+            let item = self.parse_top_level_item()?;
+            module_span = module_span.union(&item.span);
+            module.add_item(item.value)?;
         }
 
         Ok(Spanned::new(module, module_span))
     }
 
-    fn parse_function(&mut self) -> ALResult<FunctionDecl> {
+    /// Parses a single function, struct, type, module or impl declaration, or an expression.
+    /// A trailing `;` after an expression is consumed.
+    ///
+    /// Use this to build a module piece by piece, e.g. line by line in a REPL.
+    pub fn parse_top_level_item(&mut self) -> ALResult<ModuleItem> {
+        let token = self.peek()?;
+        // Peeking collected the doc comments in front of the item
+        let doc_comments = std::mem::take(&mut self.doc_comments);
+
+        match token.value {
+            Token::Identifier(Identifier::Function) => {
+                self.consume();
+                let mut function = self.parse_function(None)?;
+                function.value.doc_comments = doc_comments;
+                let span = function.span;
+                Ok(Spanned::new(ModuleItem::Function(function), span))
+            }
+            Token::Identifier(Identifier::Struct) => {
+                self.consume();
+                let struct_name = self.parse_user_defined_identifier()?;
+                let mut struct_decl = self.parse_struct()?;
+                struct_decl.value.doc_comments = doc_comments;
+                let span = struct_name.span.union(&struct_decl.span);
+                Ok(Spanned::new(
+                    ModuleItem::Struct(struct_name, struct_decl),
+                    span,
+                ))
+            }
+            Token::Identifier(Identifier::Type) => {
+                self.consume();
+                let alias_name = self.parse_user_defined_identifier()?;
+                self.consume_checked(Token::Identifier(Identifier::Assignment))?;
+                let type_id = self.parse_type()?;
+                let semicolon = self.consume_checked(Token::Identifier(Identifier::Semicolon))?;
+                let span = alias_name.span.union(&semicolon.span);
+                Ok(Spanned::new(
+                    ModuleItem::TypeAlias(alias_name, type_id),
+                    span,
+                ))
+            }
+            Token::Identifier(Identifier::Mod) => {
+                self.consume();
+                let module_name = self.parse_user_defined_identifier()?;
+                let module = self.parse_nested_module(module_name.value.clone())?;
+                let span = module_name.span.union(&module.span);
+                Ok(Spanned::new(ModuleItem::Module(module_name, module), span))
+            }
+            Token::Identifier(Identifier::Impl) => {
+                self.consume();
+                let type_name = self.parse_user_defined_identifier()?;
+                let methods = self.parse_impl_block(&type_name)?;
+                let span = token.span.union(&methods.span);
+                Ok(Spanned::new(
+                    ModuleItem::Impl(type_name, methods.value),
+                    span,
+                ))
+            }
+            _ => {
+                let expr = self.parse_expression()?;
+                let _ = self.consume_checked(Token::Identifier(Identifier::Semicolon));
+                let span = expr.span;
+                Ok(Spanned::new(ModuleItem::Expression(expr), span))
+            }
+        }
+    }
+
+    /// Parses the `{ ... }` body of `mod name { ... }`.
+    /// Nested modules can only contain functions, `impl` blocks and other modules.
+    fn parse_nested_module(&mut self, name: String) -> ALResult<Module> {
+        let mut module = Module::new(name);
+        let span = self
+            .consume_checked(Token::Identifier(Identifier::LBrace))?
+            .span;
+
+        while !self.is_next_token(Token::Identifier(Identifier::RBrace)) {
+            let Spanned::<Token> { value, span } = self.peek()?;
+            if !matches!(
+                value,
+                Token::Identifier(Identifier::Function | Identifier::Mod | Identifier::Impl)
+            ) {
+                return Err(UnexpectedToken {
+                    found: value,
+                    span,
+                    expected: "Expected function, mod or impl".into(),
+                })
+                .wrap_err("Parsing module");
+            }
+
+            let item = self.parse_top_level_item()?;
+            module.add_item(item.value)?;
+        }
+
+        let span = span.union(
+            &self
+                .consume_checked(Token::Identifier(Identifier::RBrace))?
+                .span,
+        );
+
+        Ok(Spanned::new(module, span))
+    }
+
+    /// Parses the `{ ... }` body of `impl Type { ... }`. It can only contain functions.
+    fn parse_impl_block(
+        &mut self,
+        type_name: &Spanned<String>,
+    ) -> ALResult<Vec<Spanned<FunctionDecl>>> {
+        let span = self
+            .consume_checked(Token::Identifier(Identifier::LBrace))?
+            .span;
+
+        let mut methods = Vec::new();
+        while !self.is_next_token(Token::Identifier(Identifier::RBrace)) {
+            // Peeking collected the doc comments in front of the method
+            let doc_comments = std::mem::take(&mut self.doc_comments);
+            self.consume_checked(Token::Identifier(Identifier::Function))
+                .wrap_err("Parsing impl block")?;
+            let mut method = self.parse_function(Some(type_name))?;
+            method.value.doc_comments = doc_comments;
+            methods.push(method);
+        }
+
+        let span = span.union(
+            &self
+                .consume_checked(Token::Identifier(Identifier::RBrace))?
+                .span,
+        );
+
+        Ok(Spanned::new(methods, span))
+    }
+
+    /// `self_type` is the type of the `impl` block if the function is a method.
+    fn parse_function(&mut self, self_type: Option<&Spanned<String>>) -> ALResult<FunctionDecl> {
         let function_name = self.parse_user_defined_identifier()?;
-        let proto = self.parse_function_proto(function_name.clone())?;
+        let proto = self.parse_function_proto(function_name.clone(), self_type)?;
         let body = self.parse_block_expression()?;
 
         let span = function_name.span.union(&body.span);
 
-        Ok(Spanned::new(FunctionDecl { proto, body }, span))
+        Ok(Spanned::new(
+            FunctionDecl {
+                proto,
+                body,
+                doc_comments: Vec::new(),
+            },
+            span,
+        ))
     }
 
-    fn parse_function_proto(&mut self, name: Spanned<String>) -> ALResult<FunctionProto> {
-        let args = self.parse_function_args_decl()?;
+    fn parse_function_proto(
+        &mut self,
+        name: Spanned<String>,
+        self_type: Option<&Spanned<String>>,
+    ) -> ALResult<FunctionProto> {
+        let args = self.parse_function_args_decl(self_type)?;
         let span = name.span.union(&args.span);
+        // Without an arrow the return type is inferred from the body
         let return_type =
             if let Ok(arrow) = self.consume_checked(Token::Identifier(Identifier::Arrow)) {
-                self.parse_type()?.map_span(|span| arrow.span.union(&span))
+                Some(self.parse_type()?.map_span(|span| arrow.span.union(&span)))
             } else {
-                Spanned::new(TypeID::Void, args.span.next())
+                None
             };
 
         Ok(Spanned::new(
@@ -146,13 +305,13 @@ impl Parser<'_> {
 
         let mut fields = Vec::new();
         loop {
-            fields.push(self.parse_struct_field()?);
-
             if let Ok(rbrace) = self.consume_checked(Token::Identifier(Identifier::RBrace)) {
                 let span = start_span.union(&rbrace.span);
 
                 return Ok(Spanned::new(fields, span));
             }
+
+            fields.push(self.parse_struct_field()?);
         }
     }
 
@@ -176,12 +335,15 @@ impl Parser<'_> {
         match self.peek()?.value {
             Token::Identifier(Identifier::If) => self.parse_if_expression(),
             Token::Identifier(Identifier::Loop) => self.parse_loop_expression(),
+            Token::Identifier(Identifier::For) => self.parse_for_expression(),
             Token::Identifier(Identifier::Let) => self.parse_let_expression(),
-            Token::Identifier(Identifier::LBrace) => self.parse_block_expression(),
             Token::Identifier(Identifier::Return) => self.parse_return_expression(),
-            Token::Identifier(Identifier::Break) => {
-                self.consume();
-                Ok(Spanned::new(Expr::Break, self.peek()?.span))
+            Token::Identifier(Identifier::Break) => self.parse_break_expression(),
+            Token::Identifier(Identifier::Continue) => {
+                let span = self
+                    .consume_checked(Token::Identifier(Identifier::Continue))?
+                    .span;
+                Ok(Spanned::new(Expr::Continue, span))
             }
             _ => {
                 let lhs = self.parse_primary_expression()?;
@@ -190,6 +352,16 @@ impl Parser<'_> {
         }
     }
 
+    /// Parses a statement of a block. Unlike in an expression, a block at the start of a
+    /// statement ends with its `}`, so `{ ... } - 1` is not a subtraction.
+    fn parse_statement(&mut self) -> ALResult<Expr> {
+        if self.is_next_token(Token::Identifier(Identifier::LBrace)) {
+            self.parse_block_expression()
+        } else {
+            self.parse_expression()
+        }
+    }
+
     fn parse_primary_expression(&mut self) -> ALResult<Expr> {
         let Spanned::<Token> { value, span } = self.peek()?;
 
@@ -204,35 +376,71 @@ impl Parser<'_> {
             }
             Token::Identifier(Identifier::LParen) => {
                 self.consume();
-                let expr = self.parse_expression()?;
+                let expr = self.parse_enclosed_expression()?;
                 self.consume_checked(Token::Identifier(Identifier::RParen))?;
                 Ok(expr)
             }
+            Token::Identifier(Identifier::LBrace) => self.parse_block_expression(),
             _ => Err(UnexpectedToken {
                 found: value,
-                span: self.last_offset.into(),
+                span,
                 expected: "Expected expression".into(),
             }
             .into()),
         }?;
 
-        // Check if we have a dot operator
-        while self
-            .consume_checked(Token::Identifier(Identifier::Dot))
-            .is_ok()
-        {
-            let identifier = self.parse_user_defined_identifier()?;
+        // Check if we have a dot operator, an index or a cast
+        loop {
+            if self
+                .consume_checked(Token::Identifier(Identifier::Dot))
+                .is_ok()
+            {
+                let identifier = self.parse_user_defined_identifier()?;
 
-            let rhs = self.parse_expression_function_call_or_variable(identifier)?;
-            let span = span.union(&rhs.span);
+                let rhs = self.parse_expression_function_call_or_variable(identifier)?;
+                let span = span.union(&rhs.span);
 
-            lhs = Spanned::new(
-                Expr::Dot {
-                    lhs: Box::new(lhs),
-                    rhs,
-                },
-                span,
-            );
+                lhs = Spanned::new(
+                    Expr::Dot {
+                        lhs: Box::new(lhs),
+                        rhs,
+                    },
+                    span,
+                );
+            } else if self
+                .consume_checked(Token::Identifier(Identifier::LBracket))
+                .is_ok()
+            {
+                let index = self.parse_enclosed_expression()?;
+                let r_bracket_span = self
+                    .consume_checked(Token::Identifier(Identifier::RBracket))?
+                    .span;
+                let span = span.union(&r_bracket_span);
+
+                lhs = Spanned::new(
+                    Expr::Index {
+                        expr: Box::new(lhs),
+                        index: Box::new(index),
+                    },
+                    span,
+                );
+            } else if self
+                .consume_checked(Token::Identifier(Identifier::As))
+                .is_ok()
+            {
+                let target = self.parse_type()?;
+                let span = span.union(&target.span);
+
+                lhs = Spanned::new(
+                    Expr::Cast {
+                        expr: Box::new(lhs),
+                        target,
+                    },
+                    span,
+                );
+            } else {
+                break;
+            }
         }
 
         Ok(lhs)
@@ -240,21 +448,85 @@ impl Parser<'_> {
 
     /// This parses everything that starts with an identifier. Variables, function calls, etc.
     fn parse_expression_identifier(&mut self) -> ALResult<Expr> {
-        let identifier = self.parse_user_defined_identifier()?;
-        match self.expect_token(Token::Identifier(Identifier::LBrace)) {
-            Ok(_) => self.parse_struct_literal(identifier),
-            Err(_) => self
-                .parse_expression_function_call_or_variable(identifier)
-                .map(|v| v.map_value(Into::into)),
+        let mut identifier = self.parse_user_defined_identifier()?;
+
+        // `inner::helper()` calls a function of a nested module
+        if self.is_next_token(Token::Identifier(Identifier::DoubleColon)) {
+            while self
+                .consume_checked(Token::Identifier(Identifier::DoubleColon))
+                .is_ok()
+            {
+                let segment = self.parse_user_defined_identifier()?;
+                identifier = Spanned::new(
+                    format!("{}::{}", identifier.value, segment.value),
+                    identifier.span.union(&segment.span),
+                );
+            }
+
+            let token = self.peek()?;
+            if token.value != Token::Identifier(Identifier::LParen) {
+                return Err(UnexpectedToken {
+                    found: token.value,
+                    span: token.span,
+                    expected: "Expected function call after module path".into(),
+                }
+                .into());
+            }
+        }
+
+        if self.is_struct_literal_start() {
+            self.parse_struct_literal(identifier)
+        } else {
+            self.parse_expression_function_call_or_variable(identifier)
+                .map(|v| v.map_value(Into::into))
         }
     }
 
+    /// A struct literal starts with `{ field:` or is empty. Without looking that far ahead
+    /// `if x { ... }` would be parsed as the struct literal `x { ... }`.
+    /// In conditions `x {}` is never a struct literal, so `if x {}` has an empty block.
+    fn is_struct_literal_start(&mut self) -> bool {
+        if !self.is_next_token(Token::Identifier(Identifier::LBrace)) {
+            return false;
+        }
+
+        self.input.push_end();
+        let last_offset_cache = self.last_offset;
+
+        self.consume();
+        let is_struct_literal = if self.is_next_token(Token::Identifier(Identifier::RBrace)) {
+            !self.in_condition
+        } else {
+            self.parse_user_defined_identifier().is_ok()
+                && self.is_next_token(Token::Identifier(Identifier::Colon))
+        };
+
+        self.input.reset();
+        self.last_offset = last_offset_cache;
+
+        is_struct_literal
+    }
+
+    /// Parses the condition of `if` or the iterable of `for`, which is followed by a block.
+    fn parse_condition(&mut self) -> ALResult<Expr> {
+        let outer = std::mem::replace(&mut self.in_condition, true);
+        let condition = self.parse_expression();
+        self.in_condition = outer;
+        condition
+    }
+
+    /// Parses an expression inside of `()` or `[]`, where a `{` can't start the block
+    /// of a condition around it.
+    fn parse_enclosed_expression(&mut self) -> ALResult<Expr> {
+        let outer = std::mem::replace(&mut self.in_condition, false);
+        let expr = self.parse_expression();
+        self.in_condition = outer;
+        expr
+    }
+
     fn parse_struct_literal(&mut self, identifier: Spanned<String>) -> ALResult<Expr> {
         self.consume_checked(Token::Identifier(Identifier::LBrace))?;
 
-        self.input.pop_end();
-        self.input.push_end(); // Only becaus we know we are in try_parse
-
         let mut fields = Vec::new();
         while !self.is_next_token(Token::Identifier(Identifier::RBrace)) {
             let name = self.parse_user_defined_identifier()?;
@@ -285,7 +557,7 @@ impl Parser<'_> {
             Ok(_) => {
                 let mut args = Vec::new();
                 loop {
-                    if let Ok(input) = self.parse_expression() {
+                    if let Ok(input) = self.parse_enclosed_expression() {
                         args.push(input);
                     }
 
@@ -323,6 +595,9 @@ impl Parser<'_> {
         mut lhs: Spanned<Expr>,
         precendence: i16,
     ) -> ALResult<Expr> {
+        // Whether `lhs` is a comparison that the next comparison operator continues
+        let mut in_chain = false;
+
         while let Ok(token) = self.peek() {
             let op = match Spanned::<BinaryOperator>::try_from(token) {
                 Ok(op) => op,
@@ -338,20 +613,51 @@ impl Parser<'_> {
 
             let mut rhs = self.parse_primary_expression()?;
 
-            if op.value.precedence() < self.current_precedence() {
+            let next_precedence = self.current_precedence();
+            if op.value.is_right_associative() && op.value.precedence() <= next_precedence {
+                // Right associative operators take everything with the same precedence to the right
+                rhs = self.parse_binary_expression(rhs, op.value.precedence())?;
+            } else if op.value.precedence() < next_precedence {
                 rhs = self.parse_binary_expression(rhs, op.value.precedence() + 1)?;
             }
 
             let span = lhs.span.union(&rhs.span);
 
-            lhs = Spanned::new(
-                Expr::Binary(Spanned::new(BinaryExpression::new(lhs, op, rhs), span)),
-                span,
-            );
+            let is_relational = op.value.is_relational();
+            lhs = match lhs.value {
+                // `a < b < c` is read as `a < b && b < c`, but evaluates `b` only once
+                Expr::Binary(comparison) if in_chain && is_relational => {
+                    let BinaryExpression {
+                        lhs: first,
+                        op: first_op,
+                        rhs: middle,
+                    } = comparison.value;
+                    Spanned::new(
+                        Expr::ChainedComparison(first, vec![(first_op, *middle), (op, rhs)]),
+                        span,
+                    )
+                }
+                Expr::ChainedComparison(first, mut comparisons) if in_chain && is_relational => {
+                    comparisons.push((op, rhs));
+                    Spanned::new(Expr::ChainedComparison(first, comparisons), span)
+                }
+                lhs_value => Spanned::new(
+                    Expr::Binary(Spanned::new(
+                        BinaryExpression::new(Spanned::new(lhs_value, lhs.span), op, rhs),
+                        span,
+                    )),
+                    span,
+                ),
+            };
+            in_chain = is_relational;
         }
         Ok(lhs)
     }
 
+    /// Parses `{ statement; ... tail }`.
+    /// Only the last expression before `}` may omit the `;` and becomes the value of the block.
+    /// `if`, `loop` and `for` don't need a `;` anywhere, every other statement without one is an error.
+    /// A `;` without a statement in front of it is an empty statement and skipped.
     fn parse_block_expression(&mut self) -> ALResult<Expr> {
         let mut block = Vec::new();
 
@@ -362,7 +668,12 @@ impl Parser<'_> {
         let mut return_expression = None;
 
         while !self.is_next_token(Token::Identifier(Identifier::RBrace)) {
-            let expr = self.parse_expression()?;
+            if self.is_next_token(Token::Identifier(Identifier::Semicolon)) {
+                self.consume_checked(Token::Identifier(Identifier::Semicolon))?;
+                continue;
+            }
+
+            let expr = self.parse_statement()?;
 
             // We expect a semicolon after each expression in a block, or we are at the end of the block.
             match self.consume_checked(Token::Identifier(Identifier::Semicolon)) {
@@ -376,10 +687,27 @@ impl Parser<'_> {
                 // If expressions dont need a semicolon
                 Err(_)
                     if matches!(expr.value, Expr::IfExpression { .. })
-                        || matches!(expr.value, Expr::Loop(_)) =>
+                        || matches!(expr.value, Expr::Loop(_))
+                        || matches!(expr.value, Expr::For { .. }) =>
                 {
                     block.push(expr);
                 }
+                Err(e) if e.downcast_ref::<UnexpectedToken>().is_some() => {
+                    // `1 2` is either a missing `;` or a missing operator
+                    if let Ok(Spanned::<Token> {
+                        value:
+                            Token::Literal(_)
+                            | Token::Identifier(Identifier::UserDefined(_) | Identifier::LParen),
+                        span,
+                    }) = self.peek()
+                    {
+                        return Err(MissingSeparator { span }.into());
+                    }
+                    return Err(MissingSemicolon {
+                        span: (expr.span.offset() + expr.span.len(), 0).into(),
+                    }
+                    .into());
+                }
                 Err(e) => return Err(e),
             }
         }
@@ -398,8 +726,7 @@ impl Parser<'_> {
             .consume_checked(Token::Identifier(Identifier::Return))?
             .span;
 
-        if self.is_next_token(Token::Identifier(Identifier::Semicolon)) {
-            self.consume();
+        if self.is_end_of_expression() {
             return Ok(Spanned::new(Expr::Return(None), span));
         }
 
@@ -409,6 +736,21 @@ impl Parser<'_> {
         Ok(Spanned::new(Expr::Return(Some(Box::new(expr))), span))
     }
 
+    fn parse_break_expression(&mut self) -> ALResult<Expr> {
+        let span = self
+            .consume_checked(Token::Identifier(Identifier::Break))?
+            .span;
+
+        if self.is_end_of_expression() {
+            return Ok(Spanned::new(Expr::Break(None), span));
+        }
+
+        let expr = self.parse_expression()?;
+        let span = span.union(&expr.span);
+
+        Ok(Spanned::new(Expr::Break(Some(Box::new(expr))), span))
+    }
+
     fn parse_let_expression(&mut self) -> ALResult<Expr> {
         let span_start = self
             .consume_checked(Token::Identifier(Identifier::Let))?
@@ -424,12 +766,20 @@ impl Parser<'_> {
             None
         };
 
+        // Without an initializer the variable needs a type
+        if let Some(type_span) = type_id.as_ref().map(|type_id| type_id.span) {
+            if !self.is_next_token(Token::Identifier(Identifier::Assignment)) {
+                let span = span_start.union(&type_span);
+                return Ok(Spanned::new(Expr::Let(var_name, type_id, None), span));
+            }
+        }
+
         self.consume_checked(Token::Identifier(Identifier::Assignment))?;
         let assign_to = self.parse_expression()?;
 
         let span = span_start.union(&assign_to.span);
         Ok(Spanned::new(
-            Expr::Let(var_name.clone(), type_id, Box::new(assign_to)),
+            Expr::Let(var_name.clone(), type_id, Some(Box::new(assign_to))),
             span,
         ))
     }
@@ -437,7 +787,7 @@ impl Parser<'_> {
     fn parse_if_expression(&mut self) -> ALResult<Expr> {
         self.consume_checked(Token::Identifier(Identifier::If))?;
 
-        let condition = Box::new(self.parse_expression()?);
+        let condition = Box::new(self.parse_condition()?);
         let then_block = Box::new(self.parse_block_expression()?);
 
         let mut else_if_blocks = Vec::new();
@@ -450,7 +800,7 @@ impl Parser<'_> {
         {
             match self.consume_checked(Token::Identifier(Identifier::If)) {
                 Ok(_) => else_if_blocks.push((
-                    Box::new(self.parse_expression()?),
+                    Box::new(self.parse_condition()?),
                     Box::new(self.parse_block_expression()?),
                 )),
                 Err(_) => {
@@ -460,9 +810,12 @@ impl Parser<'_> {
             }
         }
 
-        let span = condition
-            .span
-            .union(&else_block.as_ref().unwrap_or(&then_block).span);
+        // The if expression ends with the else block, the last else if block or the then block
+        let last_block = else_block
+            .as_ref()
+            .or(else_if_blocks.last().map(|(_, block)| block))
+            .unwrap_or(&then_block);
+        let span = condition.span.union(&last_block.span);
 
         Ok(Spanned::new(
             Expr::IfExpression {
@@ -483,6 +836,26 @@ impl Parser<'_> {
         let span = loop_span.union(&expr.span);
         Ok(Spanned::new(Expr::Loop(expr), span))
     }
+
+    fn parse_for_expression(&mut self) -> ALResult<Expr> {
+        let for_span = self
+            .consume_checked(Token::Identifier(Identifier::For))?
+            .span;
+        let var = self.parse_user_defined_identifier()?;
+        self.consume_checked(Token::Identifier(Identifier::In))?;
+        let iterable = Box::new(self.parse_condition()?);
+        let body = Box::new(self.parse_block_expression()?);
+
+        let span = for_span.union(&body.span);
+        Ok(Spanned::new(
+            Expr::For {
+                var,
+                iterable,
+                body,
+            },
+            span,
+        ))
+    }
 }
 
 // -------------------------------------------------------------------------------------------
@@ -498,6 +871,14 @@ impl Parser<'_> {
                 self.consume();
                 Ok(Spanned::new(name, span))
             }
+            Spanned::<Token> {
+                value: Token::Identifier(keyword),
+                span,
+            } if keyword.is_keyword() => Err(ReservedKeyword {
+                keyword: keyword.to_string(),
+                span,
+            }
+            .into()),
             tok => Err(UnexpectedToken {
                 found: tok.value,
                 span: tok.span,
@@ -512,7 +893,11 @@ impl Parser<'_> {
     /// This is a list of `name: type` pairs separated by commas.
     ///
     /// The list is enclosed in parentheses.
-    fn parse_function_args_decl(&mut self) -> ALResult<Vec<ArgumentDecl>> {
+    /// Methods can start with a plain `self`, which gets the type `self_type`.
+    fn parse_function_args_decl(
+        &mut self,
+        self_type: Option<&Spanned<String>>,
+    ) -> ALResult<Vec<ArgumentDecl>> {
         let mut args = Vec::new();
 
         let l_paren_span = self
@@ -528,8 +913,19 @@ impl Parser<'_> {
 
         loop {
             let name = self.parse_user_defined_identifier()?;
-            self.consume_checked(Token::Identifier(Identifier::Colon))?;
-            let ty = self.parse_type()?;
+            let ty = match self_type {
+                Some(self_type)
+                    if args.is_empty()
+                        && name.value == "self"
+                        && !self.is_next_token(Token::Identifier(Identifier::Colon)) =>
+                {
+                    Spanned::new(TypeID::User(self_type.value.clone()), name.span)
+                }
+                _ => {
+                    self.consume_checked(Token::Identifier(Identifier::Colon))?;
+                    self.parse_type()?
+                }
+            };
             args.push((name, ty));
 
             // No more comma. Next token must be RParen
@@ -569,6 +965,9 @@ impl Parser<'_> {
 // Parser helpers
 impl Parser<'_> {
     fn consume(&mut self) -> Option<&Spanned<Token>> {
+        self.skip_doc_comments();
+        // Doc comments only belong to the token directly after them
+        self.doc_comments.clear();
         self.input
             .consume()
             .inspect(|t| self.last_offset = t.span.offset() + t.span.len())
@@ -592,7 +991,25 @@ impl Parser<'_> {
     }
 
     fn is_next_token(&mut self, expected: Token) -> bool {
-        self.peek().map_or(false, |t| t.value == expected)
+        self.peek().is_ok_and(|t| t.value == expected)
+    }
+
+    /// Whether `return` or `break` has no value, because the next token ends the expression.
+    /// The token itself is left for the surrounding block, call or statement.
+    fn is_end_of_expression(&mut self) -> bool {
+        self.skip_doc_comments();
+        match self.input.peek() {
+            Some(token) => matches!(
+                token.value,
+                Token::Identifier(
+                    Identifier::Semicolon
+                        | Identifier::RBrace
+                        | Identifier::RParen
+                        | Identifier::Comma
+                )
+            ),
+            None => true,
+        }
     }
 
     #[allow(dead_code)]
@@ -620,7 +1037,7 @@ impl Parser<'_> {
         } else {
             Err(Error::from(UnexpectedToken {
                 found: token.value,
-                span: SourceSpan::from(self.last_offset),
+                span: token.span,
                 expected: expected.into(),
             }))
         }
@@ -628,11 +1045,43 @@ impl Parser<'_> {
 
     /// Peeks the next token in the input stream.
     /// # Errors
-    /// Returns ErrorKind::UnexpectedEOF if the input stream is empty.
+    /// Returns [`UnexpectedEof`] pointing behind the last token if the input stream is empty.
+    /// Returns [`UnexpectedCharacter`] if the tokenizer found an invalid character.
+    /// Returns [`InvalidNumber`] if the tokenizer found a malformed number.
     fn peek(&mut self) -> ALResult<Token> {
-        self.input
-            .peek()
-            .cloned()
-            .ok_or(miette::Error::msg("Unexpected EOF"))
+        self.skip_doc_comments();
+        match self.input.peek().cloned() {
+            Some(Spanned::<Token> {
+                value: Token::Invalid(c),
+                span,
+            }) => Err(UnexpectedCharacter { found: c, span }.into()),
+            Some(Spanned::<Token> {
+                value: Token::InvalidNumber(literal),
+                span,
+            }) => Err(InvalidNumber { literal, span }.into()),
+            Some(token) => Ok(token),
+            // Point at the last character of the input, an empty span would not be shown
+            None => Err(UnexpectedEof {
+                span: SourceSpan::new(
+                    self.last_offset.saturating_sub(1).into(),
+                    self.last_offset.min(1),
+                ),
+            }
+            .into()),
+        }
+    }
+
+    /// Doc comments are not part of the grammar. They are collected here and picked up by the
+    /// declaration that follows them.
+    fn skip_doc_comments(&mut self) {
+        while let Some(Spanned::<Token> {
+            value: Token::DocComment(doc),
+            ..
+        }) = self.input.peek()
+        {
+            let doc = doc.clone();
+            self.input.consume();
+            self.doc_comments.push(doc);
+        }
     }
 }
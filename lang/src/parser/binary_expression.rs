@@ -12,6 +12,10 @@ use super::expression::Expr;
 #[derive(Debug, Clone)]
 pub enum BinaryOperator {
     Assign,
+    AddAssign,
+    SubstractAssign,
+    MultiplyAssign,
+    DivideAssign,
 
     Add,
     Substract,
@@ -48,18 +52,61 @@ impl BinaryExpression {
 impl BinaryOperator {
     pub fn precedence(&self) -> i16 {
         match self {
-            BinaryOperator::Assign => 1,
+            BinaryOperator::Assign
+            | BinaryOperator::AddAssign
+            | BinaryOperator::SubstractAssign
+            | BinaryOperator::MultiplyAssign
+            | BinaryOperator::DivideAssign => 1,
             BinaryOperator::Add | BinaryOperator::Substract => 100,
             BinaryOperator::Multiply | BinaryOperator::Divide => 200,
-            BinaryOperator::And => 10,
+            BinaryOperator::And => 20,
             BinaryOperator::Or => 10,
-            BinaryOperator::Equal | BinaryOperator::NotEqual => 5,
+            BinaryOperator::Equal | BinaryOperator::NotEqual => 25,
             BinaryOperator::LessThan
             | BinaryOperator::LessThanOrEqual
             | BinaryOperator::GreaterThan
-            | BinaryOperator::GreaterThanOrEqual => 5,
+            | BinaryOperator::GreaterThanOrEqual => 30,
         }
     }
+
+    /// `<`, `<=`, `>` and `>=`. These can be chained like `a < b < c`.
+    pub fn is_relational(&self) -> bool {
+        matches!(
+            self,
+            BinaryOperator::LessThan
+                | BinaryOperator::LessThanOrEqual
+                | BinaryOperator::GreaterThan
+                | BinaryOperator::GreaterThanOrEqual
+        )
+    }
+
+    /// `=` and the compound assignments like `+=`.
+    pub fn is_assignment(&self) -> bool {
+        matches!(
+            self,
+            BinaryOperator::Assign
+                | BinaryOperator::AddAssign
+                | BinaryOperator::SubstractAssign
+                | BinaryOperator::MultiplyAssign
+                | BinaryOperator::DivideAssign
+        )
+    }
+
+    /// The operator a compound assignment applies before assigning, `+` for `+=`.
+    pub fn compound_operator(&self) -> Option<BinaryOperator> {
+        match self {
+            BinaryOperator::AddAssign => Some(BinaryOperator::Add),
+            BinaryOperator::SubstractAssign => Some(BinaryOperator::Substract),
+            BinaryOperator::MultiplyAssign => Some(BinaryOperator::Multiply),
+            BinaryOperator::DivideAssign => Some(BinaryOperator::Divide),
+            _ => None,
+        }
+    }
+
+    /// `a = b = c` should be parsed as `a = (b = c)`
+    pub fn is_right_associative(&self) -> bool {
+        self.is_assignment()
+    }
 }
 
 impl TryFrom<Spanned<Token>> for BinaryOperator {
@@ -82,6 +129,10 @@ impl TryFrom<Spanned<Token>> for BinaryOperator {
                 Ok(BinaryOperator::GreaterThanOrEqual)
             }
             Token::Identifier(Identifier::Assignment) => Ok(BinaryOperator::Assign),
+            Token::Identifier(Identifier::PlusAssignment) => Ok(BinaryOperator::AddAssign),
+            Token::Identifier(Identifier::MinusAssignment) => Ok(BinaryOperator::SubstractAssign),
+            Token::Identifier(Identifier::StarAssignment) => Ok(BinaryOperator::MultiplyAssign),
+            Token::Identifier(Identifier::SlashAssignment) => Ok(BinaryOperator::DivideAssign),
             _ => Err(miette!(
                 labels = [LabeledSpan::at(span, "here")],
                 "Invalid binary operator"
@@ -103,6 +154,10 @@ impl Display for BinaryOperator {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             BinaryOperator::Assign => write!(f, "="),
+            BinaryOperator::AddAssign => write!(f, "+="),
+            BinaryOperator::SubstractAssign => write!(f, "-="),
+            BinaryOperator::MultiplyAssign => write!(f, "*="),
+            BinaryOperator::DivideAssign => write!(f, "/="),
             BinaryOperator::Add => write!(f, "+"),
             BinaryOperator::Substract => write!(f, "-"),
             BinaryOperator::Multiply => write!(f, "*"),
@@ -2,7 +2,10 @@ use std::fmt::Display;
 
 use crate::{spanned::Spanned, tokenizer::literal::Literal};
 
-use super::{binary_expression::BinaryExpression, type_def::TypeID};
+use super::{
+    binary_expression::{BinaryExpression, BinaryOperator},
+    type_def::TypeID,
+};
 
 pub type IfCondition = (Box<Spanned<Expr>>, Box<Spanned<Expr>>);
 
@@ -27,6 +30,12 @@ pub enum Expr {
 
     FunctionCall(Spanned<String>, Vec<Spanned<Expr>>),
     Binary(Spanned<BinaryExpression>),
+    /// `a < b <= c` holds if every single comparison holds.
+    /// Each operand is evaluated exactly once, from left to right.
+    ChainedComparison(
+        Box<Spanned<Expr>>,
+        Vec<(Spanned<BinaryOperator>, Spanned<Expr>)>,
+    ),
 
     Literal(Spanned<Literal>),
     StructLiteral(Spanned<String>, Vec<(Spanned<String>, Spanned<Expr>)>),
@@ -34,7 +43,13 @@ pub enum Expr {
 
     Assignment(Spanned<String>, Box<Spanned<Expr>>),
 
-    Let(Spanned<String>, Option<Spanned<TypeID>>, Box<Spanned<Expr>>),
+    /// `let name: type = value`. The type can be omitted if there is a value and the value
+    /// can be omitted if there is a type.
+    Let(
+        Spanned<String>,
+        Option<Spanned<TypeID>>,
+        Option<Box<Spanned<Expr>>>,
+    ),
 
     IfExpression {
         if_block: IfCondition,
@@ -45,10 +60,34 @@ pub enum Expr {
 
     Loop(Box<Spanned<Expr>>),
 
+    /// Iterates over the characters of a string
+    /// # Example
+    /// ```rs
+    /// for c in "Hello" { ... }
+    /// ```
+    For {
+        var: Spanned<String>,
+        iterable: Box<Spanned<Expr>>,
+        body: Box<Spanned<Expr>>,
+    },
+
+    /// `expr[index]`
+    Index {
+        expr: Box<Spanned<Expr>>,
+        index: Box<Spanned<Expr>>,
+    },
+
+    /// `expr as type`
+    Cast {
+        expr: Box<Spanned<Expr>>,
+        target: Spanned<TypeID>,
+    },
+
     Block(Vec<Spanned<Expr>>, Option<Box<Spanned<Expr>>>),
 
     Return(Option<Box<Spanned<Expr>>>),
-    Break,
+    /// `break` or `break value`. A `loop` evaluates to the value, `for` loops ignore it.
+    Break(Option<Box<Spanned<Expr>>>),
     Continue,
 }
 
@@ -83,7 +122,10 @@ impl Display for DotExpr {
 impl Display for Expr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Expr::Dot { lhs, rhs } => write!(f, "{}.{}", lhs.value, rhs.value),
+            Expr::Dot { lhs, rhs } => match lhs.value {
+                Expr::Binary(_) => write!(f, "({}).{}", lhs.value, rhs.value),
+                _ => write!(f, "{}.{}", lhs.value, rhs.value),
+            },
             Expr::FunctionCall(name, vars) => write!(
                 f,
                 "{}({})",
@@ -94,26 +136,80 @@ impl Display for Expr {
                     .join(", ")
             ),
             Expr::Binary(expr) => {
-                write!(
-                    f,
-                    "{} {} {}",
-                    expr.value.lhs.value, expr.value.op.value, expr.value.rhs.value
-                )
+                let op = &expr.value.op.value;
+                // Only add parentheses where the precedence would otherwise change the tree
+                let needs_parens = |operand: &Expr, is_rhs: bool| match operand {
+                    Expr::Binary(inner) => {
+                        let inner = &inner.value.op.value;
+                        inner.precedence() < op.precedence()
+                            || (inner.precedence() == op.precedence()
+                                && is_rhs != op.is_right_associative())
+                    }
+                    // Without parentheses another comparison would continue the chain
+                    Expr::ChainedComparison(_, comparisons) => {
+                        comparisons[0].0.value.precedence() <= op.precedence()
+                    }
+                    _ => false,
+                };
+                let lhs = &expr.value.lhs.value;
+                let rhs = &expr.value.rhs.value;
+
+                if needs_parens(lhs, false) {
+                    write!(f, "({})", lhs)?;
+                } else {
+                    write!(f, "{}", lhs)?;
+                }
+                write!(f, " {} ", op)?;
+                if needs_parens(rhs, true) {
+                    write!(f, "({})", rhs)
+                } else {
+                    write!(f, "{}", rhs)
+                }
+            }
+            Expr::ChainedComparison(first, comparisons) => {
+                // Operands are only binary expressions with a lower precedence if they were
+                // written in parentheses
+                let write_operand = |f: &mut std::fmt::Formatter<'_>, operand: &Expr| match operand
+                {
+                    Expr::Binary(inner)
+                        if inner.value.op.value.precedence()
+                            <= comparisons[0].0.value.precedence() =>
+                    {
+                        write!(f, "({})", operand)
+                    }
+                    Expr::ChainedComparison(..) => write!(f, "({})", operand),
+                    _ => write!(f, "{}", operand),
+                };
+                write_operand(f, &first.value)?;
+                for (op, operand) in comparisons {
+                    write!(f, " {} ", op.value)?;
+                    write_operand(f, &operand.value)?;
+                }
+                Ok(())
             }
             Expr::Assignment(var, expr) => write!(f, "{} = {}", var.value, expr.value),
-            Expr::Let(var, type_id, assign) => match &type_id {
-                Some(type_id) => {
-                    write!(f, "let {}: {} = {}", var.value, type_id.value, assign.value)
+            Expr::Let(var, type_id, assign) => {
+                write!(f, "let {}", var.value)?;
+                if let Some(type_id) = type_id {
+                    write!(f, ": {}", type_id.value)?;
                 }
-                None => write!(f, "let {} = {}", var.value, assign.value),
-            },
+                if let Some(assign) = assign {
+                    write!(f, " = {}", assign.value)?;
+                }
+                Ok(())
+            }
             Expr::Literal(literal) => write!(f, "{}", literal.value),
             Expr::StructLiteral(name, fields) => {
-                write!(f, "{} {{", name.value)?;
-                for (field_name, field_expr) in fields.iter() {
-                    write!(f, "{}: {}, ", field_name.value, field_expr.value)?;
-                }
-                write!(f, "}}")
+                write!(
+                    f,
+                    "{} {{ {} }}",
+                    name.value,
+                    fields
+                        .iter()
+                        .map(|(name, expr)| format!("{}: {}", name.value, expr.value))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
             }
             Expr::Variable(name) => write!(f, "{}", name.value),
             Expr::IfExpression {
@@ -133,22 +229,41 @@ impl Display for Expr {
             Expr::Block(expr, return_expr) => {
                 write!(f, "{{")?;
                 for e in expr {
-                    write!(f, "{}, ", e.value)?;
+                    write!(f, " {};", e.value)?;
                 }
                 if let Some(return_expr) = return_expr {
-                    write!(f, "{}", return_expr.value)?;
+                    write!(f, " {}", return_expr.value)?;
                 }
-                write!(f, "}}")
+                write!(f, " }}")
             }
             Expr::Loop(expr) => write!(f, "loop {}", expr.value),
+            Expr::For {
+                var,
+                iterable,
+                body,
+            } => write!(f, "for {} in {} {}", var.value, iterable.value, body.value),
+            Expr::Index { expr, index } => match expr.value {
+                Expr::Binary(_) => write!(f, "({})[{}]", expr.value, index.value),
+                _ => write!(f, "{}[{}]", expr.value, index.value),
+            },
+            Expr::Cast { expr, target } => match expr.value {
+                Expr::Binary(_) => write!(f, "({}) as {}", expr.value, target.value),
+                _ => write!(f, "{} as {}", expr.value, target.value),
+            },
             Expr::Return(expr) => write!(
                 f,
                 "return{}",
                 expr.as_ref()
                     .map(|e| format!(" {}", e.value))
-                    .unwrap_or(";".to_string())
+                    .unwrap_or_default()
+            ),
+            Expr::Break(expr) => write!(
+                f,
+                "break{}",
+                expr.as_ref()
+                    .map(|e| format!(" {}", e.value))
+                    .unwrap_or_default()
             ),
-            Expr::Break => write!(f, "break"),
             Expr::Continue => write!(f, "continue"),
         }
     }
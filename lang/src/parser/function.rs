@@ -9,7 +9,8 @@ pub type ArgumentDecl = (Spanned<String>, Spanned<TypeID>);
 #[derive(Debug, Clone)]
 pub struct FunctionProto {
     pub name: Spanned<String>,
-    pub return_type: Spanned<TypeID>,
+    /// `None` if the function has no `->`, the return type is then inferred from the body.
+    pub return_type: Option<Spanned<TypeID>>,
     pub arguments: Spanned<Vec<ArgumentDecl>>,
 }
 
@@ -17,6 +18,8 @@ pub struct FunctionProto {
 pub struct FunctionDecl {
     pub proto: Spanned<FunctionProto>,
     pub body: Spanned<Expr>,
+    /// Lines of the `///` comments in front of the function
+    pub doc_comments: Vec<String>,
 }
 
 impl Display for FunctionProto {
@@ -28,12 +31,16 @@ impl Display for FunctionProto {
             }
             write!(f, "{}: {}", arg.0.value, arg.1.value)?;
         }
-        write!(f, ") -> {}", self.return_type.value)
+        write!(f, ")")?;
+        match &self.return_type {
+            Some(return_type) => write!(f, " -> {}", return_type.value),
+            None => Ok(()),
+        }
     }
 }
 
 impl Display for FunctionDecl {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} {{\n{}\n}}", self.proto.value, self.body.value)
+        write!(f, "{} {}", self.proto.value, self.body.value)
     }
 }
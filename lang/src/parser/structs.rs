@@ -5,32 +5,64 @@ use super::type_def::TypeID;
 #[derive(Debug, Clone)]
 pub struct Struct {
     pub fields: Vec<Spanned<(String, TypeID)>>,
+    /// Lines of the `///` comments in front of the struct
+    pub doc_comments: Vec<String>,
 }
 
 impl Struct {
     pub fn new_unit() -> Self {
         Self {
             fields: Vec::default(),
+            doc_comments: Vec::default(),
         }
     }
 
     pub fn new(fields: Vec<Spanned<(String, TypeID)>>) -> Self {
-        Self { fields }
+        Self {
+            fields,
+            doc_comments: Vec::default(),
+        }
     }
 }
 
-/// I want to make this as small as possible. The order of the fields is very importent here
-#[derive(Default, Clone)]
+/// The fields of a struct value, always in declaration order, no matter in which order the
+/// struct literal lists them. The names are stored next to the values, so a value can be
+/// printed and inspected without its type definition.
+///
+/// Cloning copies every field, including nested structs. Only opaque values are shared,
+/// a clone wraps the same Rust value as the original.
+#[derive(Debug, Default, Clone)]
 pub struct StructValue {
-    fields: Vec<Spanned<Value>>,
+    fields: Vec<(String, Spanned<Value>)>,
 }
 
 impl StructValue {
-    pub fn push_field(&mut self, value: Spanned<Value>) {
-        self.fields.push(value);
+    pub fn push_field(&mut self, name: String, value: Spanned<Value>) {
+        self.fields.push((name, value));
     }
 
     pub fn get_field(&self, index: usize) -> Option<&Spanned<Value>> {
-        self.fields.get(index)
+        self.fields.get(index).map(|(_, value)| value)
+    }
+
+    pub fn field_by_name(&self, name: &str) -> Option<&Spanned<Value>> {
+        self.fields
+            .iter()
+            .find(|(field_name, _)| field_name == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn field_by_name_mut(&mut self, name: &str) -> Option<&mut Spanned<Value>> {
+        self.fields
+            .iter_mut()
+            .find(|(field_name, _)| field_name == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Iterates the fields with their names in declaration order.
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &Spanned<Value>)> {
+        self.fields
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
     }
 }
@@ -27,12 +27,13 @@ impl TypeID {
     }
 }
 
+/// Formats the type the way it is written in source, so `TypeID::from_string` parses it back.
 impl Display for TypeID {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             TypeID::Int => write!(f, "int"),
             TypeID::Float => write!(f, "float"),
-            TypeID::String => write!(f, "string"),
+            TypeID::String => write!(f, "String"),
             TypeID::Bool => write!(f, "bool"),
             TypeID::Void => write!(f, "void"),
             TypeID::User(name) => write!(f, "{}", name),
@@ -50,4 +51,6 @@ pub enum TypeDef {
     Void,
 
     Struct(Struct),
+    /// A type registered by the host. Its values can only be created and inspected from Rust.
+    Opaque,
 }
@@ -1,4 +1,5 @@
 pub use crate::execution::*;
+#[cfg(feature = "native")]
 pub use crate::input_stream::FileInputStream;
 pub use crate::parser::{
     binary_expression::{BinaryExpression, BinaryOperator},
@@ -1,8 +1,8 @@
-use std::ops::Deref;
+use std::{fmt::Debug, ops::Deref};
 
 use miette::{SourceOffset, SourceSpan};
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct Spanned<T> {
     pub span: SourceSpan,
     pub value: T,
@@ -31,6 +31,19 @@ impl<T> Spanned<T> {
     }
 }
 
+/// Prints `value @ start..end`, so the spans don't drown the values in nested trees.
+impl<T: Debug> Debug for Spanned<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.value.fmt(f)?;
+        write!(
+            f,
+            " @ {}..{}",
+            self.span.offset(),
+            self.span.offset() + self.span.len()
+        )
+    }
+}
+
 impl<T> From<T> for Spanned<T> {
     fn from(value: T) -> Self {
         Spanned {
@@ -41,12 +54,21 @@ impl<T> From<T> for Spanned<T> {
 }
 
 pub trait SpanExt {
+    /// Returns the span covering both spans.
+    /// Empty spans are placeholders and ignored, so they don't stretch the result to offset 0.
     fn union(&self, other: &Self) -> Self;
     fn next(&self) -> Self;
 }
 
 impl SpanExt for SourceSpan {
     fn union(&self, other: &Self) -> Self {
+        if other.is_empty() {
+            return *self;
+        }
+        if self.is_empty() {
+            return *other;
+        }
+
         let start = self.offset().min(other.offset());
         let end = (self.offset() + self.len()).max(other.offset() + other.len());
         SourceSpan::new(start.into(), end - start)
@@ -64,3 +86,35 @@ impl Deref for Spanned<String> {
         &self.value
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_compact_debug() {
+        let expr = Parser::new("1 + a").parse_expression().unwrap();
+        assert_eq!(
+            format!("{:?}", expr),
+            "Binary(BinaryExpression { \
+                lhs: Literal(NumberInt(1) @ 0..1) @ 0..1, \
+                op: Add @ 2..3, \
+                rhs: Variable(\"a\" @ 4..5) @ 4..5 \
+            } @ 0..5) @ 0..5"
+        );
+    }
+
+    #[test]
+    fn test_union_ignores_empty_spans() {
+        let span = SourceSpan::from((10, 5));
+        let placeholder = SourceSpan::from((0, 0));
+
+        assert_eq!(span.union(&placeholder), span);
+        assert_eq!(placeholder.union(&span), span);
+        assert_eq!(
+            span.union(&SourceSpan::from((20, 2))),
+            SourceSpan::from((10, 12))
+        );
+    }
+}
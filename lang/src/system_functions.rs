@@ -1,6 +1,10 @@
+use miette::Error;
+
 use crate::{execution::value::Value, parser::type_def::TypeID};
 
+pub mod format;
 pub mod print;
+pub mod reflect;
 
 macro_rules! impl_system {
     (
@@ -14,7 +18,7 @@ macro_rules! impl_system {
                     Fn( $($params),* ) -> R,
                     R: Into<Value>,
         {
-            fn run(&self, resources: Vec<Value>) -> Value {
+            fn run(&self, resources: Vec<Value>) -> Result<Value, Error> {
                 fn call_inner<R: Into<Value>, $($params),*>(
                     f: impl Fn($($params),*) -> R,
                     $($params: $params),*
@@ -28,7 +32,12 @@ macro_rules! impl_system {
                     let $params = $params::retrieve(&mut iter);
                 )*
 
-                call_inner(&self.function, $($params),*).into()
+                Ok(call_inner(&self.function, $($params),*).into())
+            }
+
+            fn arg_count(&self) -> usize {
+                let params: &[&str] = &[$(stringify!($params)),*];
+                params.len()
             }
         }
     }
@@ -66,7 +75,26 @@ pub struct SystemFunction<Input, F> {
 }
 
 pub trait System {
-    fn run(&self, args: Vec<Value>) -> Value;
+    fn run(&self, args: Vec<Value>) -> Result<Value, Error>;
+
+    /// Number of arguments the function has to be called with
+    fn arg_count(&self) -> usize;
+
+    /// Variadic functions take any number of arguments after the first `arg_count`.
+    fn is_variadic(&self) -> bool {
+        false
+    }
+}
+
+/// Marker for [`System`]s that are implemented by hand instead of being a Rust function.
+pub struct CustomSystem;
+
+impl<S: System> IntoSystem<CustomSystem> for S {
+    type System = Self;
+
+    fn into_system(self) -> Self::System {
+        self
+    }
 }
 
 impl_system!();
@@ -94,7 +122,7 @@ impl_into_system!(T1, T2, T3, T4, T5, T6);
 impl SystemParam for i64 {
     fn retrieve(args: &mut impl Iterator<Item = Value>) -> Self {
         let value = args.next().unwrap();
-        match value.type_id {
+        match value.type_id() {
             TypeID::Int => value.as_int().unwrap(),
             _ => panic!("Expected an integer"),
         }
@@ -104,7 +132,7 @@ impl SystemParam for i64 {
 impl SystemParam for i32 {
     fn retrieve(args: &mut impl Iterator<Item = Value>) -> Self {
         let value = args.next().unwrap();
-        match value.type_id {
+        match value.type_id() {
             TypeID::Int => value.as_int().unwrap() as i32,
             _ => panic!("Expected an integer"),
         }
@@ -114,7 +142,7 @@ impl SystemParam for i32 {
 impl SystemParam for f64 {
     fn retrieve(args: &mut impl Iterator<Item = Value>) -> Self {
         let value = args.next().unwrap();
-        match value.type_id {
+        match value.type_id() {
             TypeID::Float => value.as_float().unwrap(),
             _ => panic!("Expected a float"),
         }
@@ -124,7 +152,7 @@ impl SystemParam for f64 {
 impl SystemParam for f32 {
     fn retrieve(args: &mut impl Iterator<Item = Value>) -> Self {
         let value = args.next().unwrap();
-        match value.type_id {
+        match value.type_id() {
             TypeID::Float => value.as_float().unwrap() as f32,
             _ => panic!("Expected a float"),
         }
@@ -134,7 +162,7 @@ impl SystemParam for f32 {
 impl SystemParam for bool {
     fn retrieve(args: &mut impl Iterator<Item = Value>) -> Self {
         let value = args.next().unwrap();
-        match value.type_id {
+        match value.type_id() {
             TypeID::Bool => value.as_bool().unwrap(),
             _ => panic!("Expected a bool"),
         }
@@ -144,9 +172,16 @@ impl SystemParam for bool {
 impl SystemParam for String {
     fn retrieve(args: &mut impl Iterator<Item = Value>) -> Self {
         let value = args.next().unwrap();
-        match value.type_id {
+        match value.type_id() {
             TypeID::String => value.as_string().unwrap().to_string(),
             _ => panic!("Expected a string"),
         }
     }
 }
+
+/// Takes the argument as it is, for functions that accept any type.
+impl SystemParam for Value {
+    fn retrieve(args: &mut impl Iterator<Item = Value>) -> Self {
+        args.next().unwrap()
+    }
+}
@@ -0,0 +1,65 @@
+use miette::{miette, Error};
+
+use crate::execution::value::Value;
+
+use super::System;
+
+/// `format(fmt, args...)` replaces every `{}` in `fmt` with the next argument.
+/// `{{` and `}}` are written as literal braces.
+pub struct Format;
+
+impl System for Format {
+    fn run(&self, args: Vec<Value>) -> Result<Value, Error> {
+        let mut args = args.into_iter();
+        let fmt = args
+            .next()
+            .expect("Argument count is checked before running");
+        let Some(fmt) = fmt.as_string() else {
+            return Err(miette!(
+                "The format string has to be a 'String', found '{}'",
+                fmt.type_id()
+            ));
+        };
+
+        let mut result = String::with_capacity(fmt.len());
+        let mut chars = fmt.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (c, chars.peek()) {
+                ('{', Some('{')) | ('}', Some('}')) => {
+                    chars.next();
+                    result.push(c);
+                }
+                ('{', Some('}')) => {
+                    chars.next();
+                    let arg = args
+                        .next()
+                        .ok_or(miette!("More placeholders than arguments in '{}'", fmt))?;
+                    result.push_str(&arg.to_string());
+                }
+                ('{' | '}', _) => {
+                    return Err(miette!(
+                        "Unmatched '{}' in format string '{}', use '{}{}' for a literal brace",
+                        c,
+                        fmt,
+                        c,
+                        c
+                    ))
+                }
+                _ => result.push(c),
+            }
+        }
+
+        if args.next().is_some() {
+            return Err(miette!("More arguments than placeholders in '{}'", fmt));
+        }
+        Ok(Value::new_string(result))
+    }
+
+    fn arg_count(&self) -> usize {
+        1
+    }
+
+    fn is_variadic(&self) -> bool {
+        true
+    }
+}
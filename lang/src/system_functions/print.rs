@@ -1,7 +1,13 @@
-pub fn print(value: String) {
+use std::io::Write;
+
+use crate::execution::value::Value;
+
+pub fn print(value: Value) {
     print!("{}", value);
+    // Without a newline stdout would hold the text back until the next one
+    let _ = std::io::stdout().flush();
 }
 
-pub fn println(value: String) {
+pub fn println(value: Value) {
     println!("{}", value);
 }
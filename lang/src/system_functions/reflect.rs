@@ -0,0 +1,5 @@
+use crate::execution::value::Value;
+
+pub fn type_of(value: Value) -> String {
+    value.type_name()
+}
@@ -15,6 +15,17 @@ pub mod token;
 pub struct Tokenizer<'a> {
     input: Box<dyn InputStream<Output = char> + 'a>,
     offset: usize,
+    keep_comments: bool,
+}
+
+/// Tokenizes the whole source. Comments are skipped.
+pub fn tokenize(source: &str) -> Vec<Spanned<Token>> {
+    Tokenizer::new(source).collect()
+}
+
+/// Tokenizes the whole source and keeps comments as [`Token::Comment`], e.g. for syntax highlighting.
+pub fn tokenize_with_comments(source: &str) -> Vec<Spanned<Token>> {
+    Tokenizer::new(source).with_comments(true).collect()
 }
 
 impl<'a> Tokenizer<'a> {
@@ -42,12 +53,31 @@ impl<'a> Tokenizer<'a> {
         Self {
             input: Box::new(input),
             offset: 0,
+            keep_comments: false,
         }
     }
 
+    /// Yield comments as [`Token::Comment`] instead of skipping them.
+    pub fn with_comments(mut self, keep_comments: bool) -> Self {
+        self.keep_comments = keep_comments;
+        self
+    }
+
     /// Returns the next token in the input stream.
     /// If the input stream is empty, `None` is returned.
+    /// Characters that can't start a token are returned as [`Token::Invalid`].
     pub fn next_token(&mut self) -> Option<Spanned<Token>> {
+        // Any mix of whitespace and comments is skipped before the next token
+        loop {
+            let token = self.read_token()?;
+            if self.keep_comments || !matches!(token.value, Token::Comment(_)) {
+                return Some(token);
+            }
+        }
+    }
+
+    /// Reads the next token including comments, which are always returned as [`Token::Comment`].
+    fn read_token(&mut self) -> Option<Spanned<Token>> {
         while let Some(c) = self.input.peek().filter(|c| c.is_whitespace()) {
             self.offset += c.len_utf8();
             self.input.advance();
@@ -114,6 +144,11 @@ impl<'a> Tokenizer<'a> {
                 Token::Identifier(Identifier::Comma),
                 (start_offset, 1).into(),
             )),
+            // '+='
+            '+' if self.consume_checked('=').is_some() => Some(Spanned::new(
+                Token::Identifier(Identifier::PlusAssignment),
+                (start_offset, 2).into(),
+            )),
             // '+'
             '+' => Some(Spanned::new(
                 Token::Identifier(Identifier::Plus),
@@ -124,11 +159,21 @@ impl<'a> Tokenizer<'a> {
                 Token::Identifier(Identifier::Arrow),
                 (start_offset, 1).into(),
             )),
+            // '-='
+            '-' if self.consume_checked('=').is_some() => Some(Spanned::new(
+                Token::Identifier(Identifier::MinusAssignment),
+                (start_offset, 2).into(),
+            )),
             // '-'
             '-' => Some(Spanned::new(
                 Token::Identifier(Identifier::Minus),
                 (start_offset, 1).into(),
             )),
+            // '*='
+            '*' if self.consume_checked('=').is_some() => Some(Spanned::new(
+                Token::Identifier(Identifier::StarAssignment),
+                (start_offset, 2).into(),
+            )),
             // '*'
             '*' => Some(Spanned::new(
                 Token::Identifier(Identifier::Star),
@@ -136,14 +181,40 @@ impl<'a> Tokenizer<'a> {
             )),
             // '//'
             '/' if self.consume_checked('/').is_some() => {
-                let _comment: String = self.consume_till("\n").into_iter().collect();
-                self.next_token()
+                let comment: String = self.consume_till("\n").into_iter().collect();
+                // '///' but not '////'
+                if let Some(doc) = comment
+                    .strip_prefix('/')
+                    .filter(|doc| !doc.starts_with('/'))
+                {
+                    let len = comment.len() + 2;
+                    let doc = doc.strip_prefix(' ').unwrap_or(doc).trim_end();
+                    Some(Spanned::new(
+                        Token::DocComment(doc.to_string()),
+                        (start_offset, len).into(),
+                    ))
+                } else {
+                    let comment = format!("//{}", comment);
+                    let len = comment.len();
+                    Some(Spanned::new(
+                        Token::Comment(comment),
+                        (start_offset, len).into(),
+                    ))
+                }
             }
             // '/*'
             '/' if self.consume_checked('*').is_some() => {
-                let _comment: String = self.consume_till("*/").into_iter().collect();
-                self.next_token()
+                let comment: String = self.consume_till("*/").into_iter().collect();
+                Some(Spanned::new(
+                    Token::Comment(format!("/*{}*/", comment)),
+                    (start_offset, self.offset - start_offset).into(),
+                ))
             }
+            // '/='
+            '/' if self.consume_checked('=').is_some() => Some(Spanned::new(
+                Token::Identifier(Identifier::SlashAssignment),
+                (start_offset, 2).into(),
+            )),
             // '/'
             '/' => Some(Spanned::new(
                 Token::Identifier(Identifier::Slash),
@@ -208,7 +279,7 @@ impl<'a> Tokenizer<'a> {
                 self.parse_string_literal(),
                 (start_offset, self.offset - start_offset).into(),
             )),
-            c if c.is_numeric() => Some(Spanned::new(
+            c if c.is_ascii_digit() => Some(Spanned::new(
                 self.parse_number_literal(current_char),
                 (start_offset, self.offset - start_offset).into(),
             )),
@@ -216,7 +287,10 @@ impl<'a> Tokenizer<'a> {
                 self.parse_identifier(current_char),
                 (start_offset, self.offset - start_offset).into(),
             )),
-            _ => None,
+            c => Some(Spanned::new(
+                Token::Invalid(c),
+                (start_offset, c.len_utf8()).into(),
+            )),
         }
     }
 
@@ -237,6 +311,17 @@ impl<'a> Tokenizer<'a> {
 
                 if next == '\"' {
                     string.push('\"');
+                } else if next == '\n' || (next == '\r' && self.input.peek() == Some('\n')) {
+                    if next == '\r' {
+                        self.offset += 1;
+                        self.input.advance();
+                    }
+                    // A `\` at the end of a line continues the string without the line break.
+                    // The indentation of the next line is skipped as well.
+                    while let Some(c) = self.input.peek().filter(|c| *c == ' ' || *c == '\t') {
+                        self.offset += c.len_utf8();
+                        self.input.advance();
+                    }
                 }
             } else {
                 string.push(c);
@@ -248,13 +333,14 @@ impl<'a> Tokenizer<'a> {
     /// Parses a number literal starting with the given character.
     /// A number literal is a sequence of digits and an optional decimal point.
     ///
-    /// Returns a `Token::Literal` with the parsed number.
+    /// Returns a `Token::Literal` with the parsed number, or `Token::InvalidNumber` if the number
+    /// is malformed or doesn't fit into its type.
     fn parse_number_literal(&mut self, first_char: char) -> Token {
         let mut number = String::new();
         number.push(first_char);
 
         while let Some(c) = self.input.peek() {
-            if c.is_numeric() || c == '.' {
+            if c.is_ascii_digit() || c == '.' {
                 number.push(c);
                 self.offset += c.len_utf8();
                 self.input.advance();
@@ -263,11 +349,15 @@ impl<'a> Tokenizer<'a> {
             }
         }
 
-        if number.contains('.') {
-            Token::Literal(Literal::NumberFloat(number.parse().unwrap()))
-        } else {
-            Token::Literal(Literal::NumberInt(number.parse().unwrap()))
-        }
+        // A float needs digits on both sides of exactly one dot
+        let literal = match number.split_once('.') {
+            Some((_, fraction)) if fraction.is_empty() || fraction.contains('.') => None,
+            Some(_) => number.parse().ok().map(Literal::NumberFloat),
+            None => number.parse().ok().map(Literal::NumberInt),
+        };
+        literal
+            .map(Token::Literal)
+            .unwrap_or(Token::InvalidNumber(number))
     }
 
     /// Parses an identifier starting with the given character.
@@ -306,29 +396,16 @@ impl Tokenizer<'_> {
         assert!(!expected.is_empty(), "expected must not be empty");
         let mut buffer = String::new();
 
-        let expected = expected.chars().collect::<Vec<_>>();
-        let expected = expected.as_slice();
-
+        // Every consumed char goes into the buffer, so a partial match of `expected`
+        // (like the lone `*` in `/* a * b */`) is kept and can still start a new match.
         while let Some(c) = self.input.next() {
             self.offset += c.len_utf8();
-            if c == expected[0] {
-                let mut found = true;
-                for e in &expected[1..] {
-                    let next_input = self.input.next()?;
-                    self.offset += next_input.len_utf8();
-
-                    if next_input != *e {
-                        found = false;
-                        break;
-                    }
-                }
+            buffer.push(c);
 
-                if found {
-                    break;
-                }
+            if buffer.ends_with(expected) {
+                buffer.truncate(buffer.len() - expected.len());
+                break;
             }
-
-            buffer.push(c);
         }
         Some(buffer)
     }
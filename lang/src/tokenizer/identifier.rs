@@ -42,6 +42,14 @@ pub enum Identifier {
 
     /// '='
     Assignment,
+    /// '+='
+    PlusAssignment,
+    /// '-='
+    MinusAssignment,
+    /// '*='
+    StarAssignment,
+    /// '/='
+    SlashAssignment,
     /// '=='
     Equals,
     /// '!='
@@ -87,6 +95,8 @@ pub enum Identifier {
     For,
     /// 'loop'
     Loop,
+    /// 'in'
+    In,
     /// 'return'
     Return,
     /// 'break'
@@ -96,6 +106,14 @@ pub enum Identifier {
 
     /// 'struct"
     Struct,
+    /// 'type'
+    Type,
+    /// 'mod'
+    Mod,
+    /// 'as'
+    As,
+    /// 'impl'
+    Impl,
 }
 
 impl Identifier {
@@ -108,15 +126,45 @@ impl Identifier {
             "if" => Self::If,
             "else" => Self::Else,
             "loop" => Self::Loop,
+            "in" => Self::In,
             "for" => Self::For,
             "while" => Self::While,
             "return" => Self::Return,
             "break" => Self::Break,
             "continue" => Self::Continue,
             "struct" => Self::Struct,
+            "type" => Self::Type,
+            "mod" => Self::Mod,
+            "as" => Self::As,
+            "impl" => Self::Impl,
             _ => Self::UserDefined(s),
         }
     }
+
+    /// Returns true for words that are reserved by the language and can't be used as names.
+    pub fn is_keyword(&self) -> bool {
+        matches!(
+            self,
+            Self::Function
+                | Self::Let
+                | Self::True
+                | Self::False
+                | Self::If
+                | Self::Else
+                | Self::Loop
+                | Self::In
+                | Self::For
+                | Self::While
+                | Self::Return
+                | Self::Break
+                | Self::Continue
+                | Self::Struct
+                | Self::Type
+                | Self::Mod
+                | Self::As
+                | Self::Impl
+        )
+    }
 }
 
 impl Display for Identifier {
@@ -140,6 +188,10 @@ impl Display for Identifier {
             Identifier::Slash => write!(f, "/"),
             Identifier::Modulus => write!(f, "%"),
             Identifier::Assignment => write!(f, "="),
+            Identifier::PlusAssignment => write!(f, "+="),
+            Identifier::MinusAssignment => write!(f, "-="),
+            Identifier::StarAssignment => write!(f, "*="),
+            Identifier::SlashAssignment => write!(f, "/="),
             Identifier::Equals => write!(f, "=="),
             Identifier::NotEquals => write!(f, "!="),
             Identifier::GreaterThan => write!(f, ">"),
@@ -158,11 +210,16 @@ impl Display for Identifier {
             Identifier::Else => write!(f, "else"),
             Identifier::For => write!(f, "for"),
             Identifier::Loop => write!(f, "loop"),
+            Identifier::In => write!(f, "in"),
             Identifier::While => write!(f, "while"),
             Identifier::Return => write!(f, "return"),
             Identifier::Break => write!(f, "break"),
             Identifier::Continue => write!(f, "continue"),
             Identifier::Struct => write!(f, "struct"),
+            Identifier::Type => write!(f, "type"),
+            Identifier::Mod => write!(f, "mod"),
+            Identifier::As => write!(f, "as"),
+            Identifier::Impl => write!(f, "impl"),
         }
     }
 }
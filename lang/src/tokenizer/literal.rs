@@ -14,8 +14,10 @@ impl Display for Literal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Literal::NumberInt(num) => write!(f, "{}", num),
+            // Keep the decimal point, so the literal stays a float
+            Literal::NumberFloat(num) if num.fract() == 0.0 => write!(f, "{:.1}", num),
             Literal::NumberFloat(num) => write!(f, "{}", num),
-            Literal::String(s) => write!(f, "\"{}\"", s),
+            Literal::String(s) => write!(f, "\"{}\"", s.replace('"', "\\\"")),
             Literal::Bool(b) => write!(f, "{}", b),
         }
     }
@@ -8,6 +8,14 @@ pub enum Token {
     Identifier(Identifier),
     /// Literal
     Literal(Literal),
+    /// A character that is not part of the language
+    Invalid(char),
+    /// A number literal that can't be parsed, like `1.2.3` or `1.`
+    InvalidNumber(String),
+    /// A comment including its delimiters. Only produced by [`Tokenizer::with_comments`](super::Tokenizer::with_comments)
+    Comment(String),
+    /// The text of a `///` comment, without the slashes
+    DocComment(String),
 }
 
 impl Display for Token {
@@ -15,6 +23,10 @@ impl Display for Token {
         match self {
             Self::Identifier(identifier) => write!(f, "{}", identifier),
             Self::Literal(literal) => write!(f, "{}", literal),
+            Self::Invalid(c) => write!(f, "{}", c),
+            Self::InvalidNumber(number) => write!(f, "{}", number),
+            Self::Comment(comment) => write!(f, "{}", comment),
+            Self::DocComment(doc) => write!(f, "/// {}", doc),
         }
     }
 }
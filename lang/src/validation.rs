@@ -0,0 +1,186 @@
+//! Checks that run on the parsed AST before anything gets executed.
+use miette::{miette, Error, LabeledSpan};
+
+use crate::{
+    error::{InvalidControllFlow, UnusedVariable},
+    module::Module,
+    parser::{
+        binary_expression::BinaryExpression, expression::Expr, function::FunctionDecl,
+        type_def::TypeID,
+    },
+    spanned::Spanned,
+    visit::{walk_expr, walk_function, Visitor},
+};
+
+pub fn validate_module(module: &Module) -> Result<(), Error> {
+    validate_nested_module(module, &[])
+}
+
+/// Validates `module`, which is nested in `enclosing`, outermost first.
+fn validate_nested_module(module: &Module, enclosing: &[&Module]) -> Result<(), Error> {
+    for (name, _) in module.type_aliases() {
+        if module
+            .resolve_type(&TypeID::User(name.value.clone()))
+            .is_none()
+        {
+            return Err(miette!(
+                labels = vec![LabeledSpan::at(name.span, "here")],
+                "Type alias '{}' refers to itself",
+                name.value
+            ));
+        }
+    }
+
+    for function in module.functions() {
+        validate_function(function)?;
+    }
+    for (type_name, method) in module.methods() {
+        let is_struct = std::iter::once(module)
+            .chain(enclosing.iter().copied())
+            .any(|module| module.find_struct(&type_name.value).is_some());
+        if !is_struct {
+            return Err(miette!(
+                labels = vec![LabeledSpan::at(type_name.span, "here")],
+                "Methods can only be defined for structs, '{}' is not a struct",
+                type_name.value
+            ));
+        }
+        validate_function(method)?;
+    }
+    let enclosing = [enclosing, &[module]].concat();
+    for (_, nested) in module.modules() {
+        validate_nested_module(&nested.value, &enclosing)?;
+    }
+    Ok(())
+}
+
+pub fn validate_function(function: &Spanned<FunctionDecl>) -> Result<(), Error> {
+    let mut check = ControllFlowCheck::default();
+    check.visit_function(function);
+    match check.error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+/// Reports `break` and `continue` that are not inside of a loop.
+/// Only the first one is reported.
+#[derive(Default)]
+struct ControllFlowCheck {
+    in_loop: bool,
+    error: Option<Error>,
+}
+
+impl ControllFlowCheck {
+    fn visit_loop_body(&mut self, body: &Spanned<Expr>) {
+        let in_loop = std::mem::replace(&mut self.in_loop, true);
+        self.visit_expr(body);
+        self.in_loop = in_loop;
+    }
+}
+
+impl<'ast> Visitor<'ast> for ControllFlowCheck {
+    fn visit_expr(&mut self, expr: &'ast Spanned<Expr>) {
+        if self.error.is_some() {
+            return;
+        }
+        match &expr.value {
+            Expr::Break(_) if !self.in_loop => {
+                self.error = Some(InvalidControllFlow::Break { span: expr.span }.into())
+            }
+            Expr::Continue if !self.in_loop => {
+                self.error = Some(InvalidControllFlow::Continue { span: expr.span }.into())
+            }
+            Expr::Loop(body) => self.visit_loop_body(body),
+            Expr::For { iterable, body, .. } => {
+                self.visit_expr(iterable);
+                self.visit_loop_body(body);
+            }
+            _ => walk_expr(self, expr),
+        }
+    }
+}
+
+/// Finds the `let` bindings that are never read. Assigning to a variable doesn't count as a read.
+/// Names starting with `_` are never reported.
+/// This is not part of [`validate_module`], callers have to opt in by calling it.
+pub fn find_unused_variables(module: &Module) -> Vec<UnusedVariable> {
+    let mut collector = UnusedVariables::default();
+    collector.visit_module(module);
+    collector.unused
+}
+
+/// A `let` binding and whether it was read
+struct Binding<'a> {
+    name: &'a Spanned<String>,
+    read: bool,
+}
+
+/// Like the interpreter, only function bodies and `for` bodies open a new scope.
+#[derive(Default)]
+struct UnusedVariables<'ast> {
+    scopes: Vec<Vec<Binding<'ast>>>,
+    unused: Vec<UnusedVariable>,
+}
+
+impl UnusedVariables<'_> {
+    fn report_scope(&mut self) {
+        self.unused.extend(
+            self.scopes
+                .pop()
+                .into_iter()
+                .flatten()
+                .filter(|binding| !binding.read && !binding.name.value.starts_with('_'))
+                .map(|binding| UnusedVariable {
+                    name: binding.name.value.clone(),
+                    span: binding.name.span,
+                }),
+        );
+    }
+}
+
+impl<'ast> Visitor<'ast> for UnusedVariables<'ast> {
+    fn visit_function(&mut self, function: &'ast Spanned<FunctionDecl>) {
+        self.scopes.push(Vec::new());
+        walk_function(self, function);
+        self.report_scope();
+    }
+
+    fn visit_expr(&mut self, expr: &'ast Spanned<Expr>) {
+        match &expr.value {
+            Expr::Variable(name) => {
+                if let Some(binding) = self
+                    .scopes
+                    .iter_mut()
+                    .rev()
+                    .flat_map(|scope| scope.iter_mut().rev())
+                    .find(|binding| binding.name.value == name.value)
+                {
+                    binding.read = true;
+                }
+            }
+            Expr::Let(name, _, value) => {
+                // The value can't refer to the variable that is being declared
+                if let Some(value) = value {
+                    self.visit_expr(value);
+                }
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.push(Binding { name, read: false });
+                }
+            }
+            Expr::Binary(Spanned::<BinaryExpression> {
+                value: BinaryExpression { lhs, op, rhs },
+                ..
+            }) if op.value.is_assignment() && matches!(lhs.value, Expr::Variable(_)) => {
+                self.visit_expr(rhs)
+            }
+            Expr::For { iterable, body, .. } => {
+                self.visit_expr(iterable);
+                self.scopes.push(Vec::new());
+                self.visit_expr(body);
+                self.report_scope();
+            }
+            _ => walk_expr(self, expr),
+        }
+    }
+}
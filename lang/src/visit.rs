@@ -0,0 +1,112 @@
+//! Traversal of the AST for analysis passes.
+//! A pass implements [`Visitor`] and overrides only the nodes it cares about. Overridden methods
+//! call the matching `walk_*` function to keep visiting the children.
+use crate::{
+    module::Module,
+    parser::{
+        binary_expression::BinaryExpression,
+        expression::{DotExpr, Expr},
+        function::FunctionDecl,
+    },
+    spanned::Spanned,
+};
+
+pub trait Visitor<'ast> {
+    /// Visits the functions and methods of the module and its nested modules.
+    fn visit_module(&mut self, module: &'ast Module) {
+        walk_module(self, module)
+    }
+
+    fn visit_function(&mut self, function: &'ast Spanned<FunctionDecl>) {
+        walk_function(self, function)
+    }
+
+    fn visit_expr(&mut self, expr: &'ast Spanned<Expr>) {
+        walk_expr(self, expr)
+    }
+}
+
+pub fn walk_module<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, module: &'ast Module) {
+    for function in module.functions() {
+        visitor.visit_function(function);
+    }
+    for (_, method) in module.methods() {
+        visitor.visit_function(method);
+    }
+    for (_, nested) in module.modules() {
+        visitor.visit_module(&nested.value);
+    }
+}
+
+pub fn walk_function<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    function: &'ast Spanned<FunctionDecl>,
+) {
+    visitor.visit_expr(&function.value.body);
+}
+
+/// Visits the direct children of `expr` in the order they are evaluated.
+pub fn walk_expr<'ast, V: Visitor<'ast> + ?Sized>(visitor: &mut V, expr: &'ast Spanned<Expr>) {
+    match &expr.value {
+        Expr::Dot { lhs, rhs } => {
+            visitor.visit_expr(lhs);
+            if let DotExpr::FunctionCall(_, args) = &rhs.value {
+                args.iter().for_each(|arg| visitor.visit_expr(arg));
+            }
+        }
+        Expr::FunctionCall(_, args) => args.iter().for_each(|arg| visitor.visit_expr(arg)),
+        Expr::Binary(Spanned::<BinaryExpression> {
+            value: BinaryExpression { lhs, rhs, .. },
+            ..
+        }) => {
+            visitor.visit_expr(lhs);
+            visitor.visit_expr(rhs);
+        }
+        Expr::ChainedComparison(first, comparisons) => {
+            visitor.visit_expr(first);
+            comparisons
+                .iter()
+                .for_each(|(_, operand)| visitor.visit_expr(operand));
+        }
+        Expr::StructLiteral(_, fields) => fields
+            .iter()
+            .for_each(|(_, field)| visitor.visit_expr(field)),
+        Expr::Assignment(_, value) => visitor.visit_expr(value),
+        Expr::Let(_, _, value) => value.iter().for_each(|value| visitor.visit_expr(value)),
+        Expr::IfExpression {
+            if_block: (condition, block),
+            else_if_blocks,
+            else_block,
+        } => {
+            visitor.visit_expr(condition);
+            visitor.visit_expr(block);
+            for (condition, block) in else_if_blocks {
+                visitor.visit_expr(condition);
+                visitor.visit_expr(block);
+            }
+            else_block
+                .iter()
+                .for_each(|block| visitor.visit_expr(block));
+        }
+        Expr::Loop(body) => visitor.visit_expr(body),
+        Expr::For { iterable, body, .. } => {
+            visitor.visit_expr(iterable);
+            visitor.visit_expr(body);
+        }
+        Expr::Index { expr, index } => {
+            visitor.visit_expr(expr);
+            visitor.visit_expr(index);
+        }
+        Expr::Cast { expr, .. } => visitor.visit_expr(expr),
+        Expr::Block(statements, return_expr) => {
+            statements
+                .iter()
+                .for_each(|statement| visitor.visit_expr(statement));
+            return_expr.iter().for_each(|expr| visitor.visit_expr(expr));
+        }
+        Expr::Return(value) | Expr::Break(value) => {
+            value.iter().for_each(|value| visitor.visit_expr(value))
+        }
+        Expr::Literal(_) | Expr::Variable(_) | Expr::Continue => {}
+    }
+}
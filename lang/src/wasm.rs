@@ -0,0 +1,40 @@
+//! Entry points for running the language in the browser.
+//! Build with `--no-default-features --features wasm` for `wasm32-unknown-unknown`.
+use std::{cell::RefCell, rc::Rc};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    execution::{value::Value, ExecutionContext},
+    parser::Parser,
+};
+
+/// Parses and runs `source`.
+/// Everything the script prints is captured and returned, because there is no stdout in the browser.
+/// Parse and runtime errors are returned as rendered reports.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn run_source(source: &str) -> Result<String, String> {
+    let output = Rc::new(RefCell::new(String::new()));
+    let print_output = output.clone();
+    let println_output = output.clone();
+
+    Parser::new(source)
+        .parse_module()
+        .and_then(|module| {
+            ExecutionContext::new(&module)
+                .register_system_function("print", move |value: Value| {
+                    print_output.borrow_mut().push_str(&value.to_string());
+                })
+                .register_system_function("println", move |value: Value| {
+                    let mut output = println_output.borrow_mut();
+                    output.push_str(&value.to_string());
+                    output.push('\n');
+                })
+                .execute()
+        })
+        .map_err(|e| format!("{:?}", e.with_source_code(source.to_string())))?;
+
+    let output = output.borrow().clone();
+    Ok(output)
+}
@@ -0,0 +1,1258 @@
+use std::{
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use lang::{
+    error::{
+        InvalidControllFlow, InvalidMainReturnType, InvalidNumberOfArguments, InvalidStructLiteral,
+        LimitExceeded, TypeMismatch,
+    },
+    prelude::*,
+};
+
+#[test]
+fn test_max_call_depth() {
+    let input = r#"
+        fn recurse(n: int) -> int {
+            return recurse(n + 1);
+        }
+
+        fn main() {
+            recurse(0);
+        }
+    "#;
+
+    let module = Parser::new(input).parse_module().unwrap();
+    let err = ExecutionContext::new(&module)
+        .with_max_call_depth(32)
+        .execute()
+        .unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<LimitExceeded>(),
+        Some(LimitExceeded::CallDepth { limit: 32, .. })
+    ));
+}
+
+#[test]
+fn test_timeout() {
+    let input = r#"
+        fn main() {
+            loop {}
+        }
+    "#;
+
+    let module = Parser::new(input).parse_module().unwrap();
+    let err = ExecutionContext::new(&module)
+        .with_timeout(Duration::from_millis(10))
+        .execute()
+        .unwrap_err();
+
+    assert!(matches!(
+        err.downcast_ref::<LimitExceeded>(),
+        Some(LimitExceeded::Timeout { .. })
+    ));
+}
+
+#[test]
+fn test_run_source_captures_output() {
+    let input = r#"
+        fn main() {
+            print("Hello, ");
+            println("World!");
+        }
+    "#;
+
+    assert_eq!(lang::wasm::run_source(input).unwrap(), "Hello, World!\n");
+    assert!(lang::wasm::run_source("fn main() { undefined(); }").is_err());
+}
+
+/// Runs the `run` binary, so the default `print` and `println` write to a real stdout.
+#[cfg(feature = "native")]
+#[test]
+fn test_print_any_value() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_run"))
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/print.al"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "42\ntrue\n1.5Point { x: 1, y: 2 }\n"
+    );
+}
+
+#[cfg(feature = "native")]
+#[test]
+fn test_run_arguments() {
+    let run = |args: &[&str]| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_run"))
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&["--unknown"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Unknown option '--unknown'"));
+
+    let output = run(&["--help"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .starts_with("Usage:"));
+
+    // Everything after `--` is the input file
+    let output = run(&["--", concat!(env!("CARGO_MANIFEST_DIR"), "/tests/print.al")]);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .starts_with("42\n"));
+}
+
+/// Without `--max-call-depth` the `run` binary still stops runaway recursion before the stack
+/// overflows.
+#[cfg(feature = "native")]
+#[test]
+fn test_default_max_call_depth() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_run"))
+        .args(["--timeout", "100"])
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/recursion.al"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Maximum call depth of 1000 exceeded"));
+}
+
+#[test]
+fn test_controll_flow_outside_loop() {
+    let input = r#"
+        fn main() {
+            loop {
+                if true {
+                    break;
+                }
+                continue;
+            }
+            break;
+        }
+    "#;
+
+    let module = Parser::new(input).parse_module().unwrap();
+    let err = ExecutionContext::new(&module).execute().unwrap_err();
+    let Some(InvalidControllFlow::Break { span }) = err.downcast_ref::<InvalidControllFlow>()
+    else {
+        panic!("Expected break outside of loop, got {:?}", err);
+    };
+    assert_eq!(&input[span.offset()..span.offset() + span.len()], "break");
+
+    let module = Parser::new("fn main() { continue; }")
+        .parse_module()
+        .unwrap();
+    let err = ExecutionContext::new(&module).execute().unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<InvalidControllFlow>(),
+        Some(InvalidControllFlow::Continue { .. })
+    ));
+}
+
+#[test]
+fn test_break_with_value() {
+    let input = r#"
+        fn main() -> int {
+            let i = 0;
+            let x = loop {
+                i = i + 1;
+                if i == 5 {
+                    break i * 2;
+                }
+            };
+            x
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(10));
+
+    let input = r#"
+        fn main() -> int {
+            let x = loop { break; };
+            if typeof(x) == "void" { 1 } else { 0 }
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(1));
+}
+
+#[test]
+fn test_declaration_order() {
+    let input = r#"
+        fn main() -> int {
+            let point = origin();
+            if is_even(10) && is_odd(7) {
+                return point.x;
+            }
+            return 0;
+        }
+
+        fn is_even(n: int) -> bool {
+            if n == 0 {
+                return true;
+            }
+            is_odd(n - 1)
+        }
+
+        fn is_odd(n: int) -> bool {
+            if n == 0 {
+                return false;
+            }
+            is_even(n - 1)
+        }
+
+        fn origin() -> Point {
+            Point { x: 42, y: 0 }
+        }
+
+        struct Point {
+            x: int;
+            y: int;
+        }
+    "#;
+
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(42));
+}
+
+#[test]
+fn test_main_exit_code() {
+    let module = Parser::new("fn main() -> int { return 3; }")
+        .parse_module()
+        .unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(3));
+
+    let module = Parser::new("fn main() -> bool { return true; }")
+        .parse_module()
+        .unwrap();
+    let err = ExecutionContext::new(&module).execute().unwrap_err();
+    assert!(err.downcast_ref::<InvalidMainReturnType>().is_some());
+}
+
+#[test]
+fn test_chained_comparison() {
+    let module = Parser::new("fn main() -> int { if 1 < 2 < 3 { 1 } else { 0 } }")
+        .parse_module()
+        .unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(1));
+
+    let module = Parser::new("fn main() -> int { if 1 < 3 < 2 { 1 } else { 0 } }")
+        .parse_module()
+        .unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(0));
+
+    // The operand in the middle is only evaluated once
+    let calls = Arc::new(AtomicI64::new(0));
+    let counter = calls.clone();
+    let module = Parser::new("fn main() -> int { if 1 < f() <= 2 < 3 { 1 } else { 0 } }")
+        .parse_module()
+        .unwrap();
+    let result = ExecutionContext::new(&module)
+        .register_system_function("f", move || counter.fetch_add(1, Ordering::SeqCst) + 2)
+        .execute()
+        .unwrap();
+    assert_eq!(result.value.as_int(), Some(1));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_value_passthrough() {
+    let input = r#"
+        struct Point {
+            x: int;
+        }
+
+        fn main() -> int {
+            let s: String = identity("a");
+            let p: Point = identity(Point { x: 2 });
+            identity(p.x) + identity(40)
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module)
+        .register_system_function("identity", |value: value::Value| value)
+        .execute()
+        .unwrap();
+    assert_eq!(result.value.as_int(), Some(42));
+}
+
+#[test]
+fn test_nested_module() {
+    let input = r#"
+        mod inner {
+            fn helper(n: int) -> int {
+                double(n) + 1
+            }
+
+            fn double(n: int) -> int {
+                n * 2
+            }
+
+            mod deeper {
+                fn value() -> int {
+                    20
+                }
+            }
+        }
+
+        fn double(n: int) -> int {
+            0
+        }
+
+        fn main() -> int {
+            inner::helper(inner::deeper::value())
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(41));
+
+    let module = Parser::new("mod inner {} fn main() { inner::missing(); }")
+        .parse_module()
+        .unwrap();
+    assert!(ExecutionContext::new(&module).execute().is_err());
+}
+
+#[test]
+fn test_nested_module_sees_enclosing_modules() {
+    let input = r#"
+        type Id = int;
+
+        fn g() -> int {
+            1
+        }
+
+        mod m {
+            fn f(x: int) -> int {
+                let y: Id = x;
+                y + g() + inner::h()
+            }
+
+            fn k() -> int {
+                100
+            }
+
+            mod inner {
+                fn h() -> int {
+                    g() + k()
+                }
+            }
+        }
+
+        fn main() -> int {
+            m::f(40)
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(142));
+}
+
+#[test]
+fn test_break_in_argument_restores_module() {
+    let input = r#"
+        mod inner {
+            fn helper() -> int {
+                2
+            }
+
+            fn take(a: int) -> int {
+                a
+            }
+        }
+
+        fn helper() -> int {
+            1
+        }
+
+        fn main() -> int {
+            loop {
+                inner::take({ break; 0 });
+            }
+            helper()
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(1));
+}
+
+#[test]
+fn test_opaque_type() {
+    struct Counter(i64);
+
+    let input = r#"
+        fn next(counter: Counter) -> Counter {
+            counter_next(counter)
+        }
+
+        fn main() -> int {
+            let counter: Counter = counter_new(40);
+            counter_get(next(next(counter)))
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module)
+        .register_type("Counter", TypeDef::Opaque)
+        .register_system_function("counter_new", |start: i64| {
+            value::Value::new_opaque("Counter", Counter(start))
+        })
+        .register_system_function("counter_next", |counter: value::Value| {
+            let Counter(count) = counter.as_opaque::<Counter>().unwrap();
+            value::Value::new_opaque("Counter", Counter(count + 1))
+        })
+        .register_system_function("counter_get", |counter: value::Value| {
+            counter.as_opaque::<Counter>().unwrap().0
+        })
+        .execute()
+        .unwrap();
+    assert_eq!(result.value.as_int(), Some(42));
+}
+
+#[test]
+fn test_unsupported_operators() {
+    struct Handle;
+
+    for operation in ["-", "*", "/", "<", ">", "+"] {
+        let input = format!("fn main() {{ handle() {operation} handle(); }}");
+        let module = Parser::new(input.as_str()).parse_module().unwrap();
+        let result = ExecutionContext::new(&module)
+            .register_type("Handle", TypeDef::Opaque)
+            .register_system_function("handle", || value::Value::new_opaque("Handle", Handle))
+            .execute();
+        assert!(result.is_err(), "handle() {operation} handle()");
+    }
+
+    for input in [
+        r#"fn main() { "a" - "b"; }"#,
+        "struct P { x: int; } fn main() { P { x: 1 } + P { x: 1 }; }",
+        "struct P { x: int; } fn main() { P { x: 1 } < P { x: 1 }; }",
+        "struct P { x: int; } fn main() { 1 + P { x: 1 }; }",
+    ] {
+        let module = Parser::new(input).parse_module().unwrap();
+        assert!(ExecutionContext::new(&module).execute().is_err(), "{input}");
+    }
+}
+
+#[test]
+fn test_string_indexing() {
+    let input = r#"
+        fn main() {
+            let ascii = "Hello";
+            println(ascii[0] + ascii[4]);
+            let multi_byte = "häßlich";
+            println(multi_byte[1] + multi_byte[2]);
+        }
+    "#;
+    assert_eq!(lang::wasm::run_source(input).unwrap(), "Ho\näß\n");
+
+    let err = lang::wasm::run_source(r#"fn main() { let s = "äb"; s[2]; }"#).unwrap_err();
+    assert!(err.contains("Index 2 is out of bounds for a string of length 2"));
+}
+
+#[test]
+fn test_string_iteration() {
+    let input = r#"
+        fn main() {
+            let reversed = "";
+            for c in "aßc" {
+                if c == "ß" {
+                    continue;
+                }
+                reversed = c + reversed;
+            }
+            println(reversed);
+        }
+    "#;
+    assert_eq!(lang::wasm::run_source(input).unwrap(), "ca\n");
+}
+
+#[test]
+fn test_struct_literal_field_order() {
+    let input = r#"
+        struct Pair {
+            first: int;
+            second: int;
+        }
+
+        fn main() {
+            let a = Pair { second: 2, first: 1 };
+            let b = Pair { first: 1, second: 2 };
+            println(a.first);
+            println(a.second);
+            println(a == b);
+            println(a);
+        }
+    "#;
+    assert_eq!(
+        lang::wasm::run_source(input).unwrap(),
+        "1\n2\ntrue\nPair { first: 1, second: 2 }\n"
+    );
+}
+
+#[test]
+fn test_empty_struct_literal() {
+    let input = r#"
+        struct E {}
+
+        fn main() {
+            let e = E {};
+            if e == (E {}) {
+                println(e);
+            }
+        }
+    "#;
+    assert_eq!(lang::wasm::run_source(input).unwrap(), "E {  }\n");
+}
+
+#[test]
+fn test_struct_literal_reports_all_invalid_fields() {
+    let input = r#"
+        struct Point {
+            x: int;
+            y: int;
+            z: int;
+        }
+
+        fn main() {
+            let p = Point { y: 1, w: 2 };
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let err = ExecutionContext::new(&module).execute().unwrap_err();
+    let Some(InvalidStructLiteral {
+        missing,
+        unknown,
+        labels,
+        ..
+    }) = err.downcast_ref::<InvalidStructLiteral>()
+    else {
+        panic!("Expected invalid struct literal, got {:?}", err);
+    };
+    assert_eq!(missing, &["x", "z"]);
+    assert_eq!(unknown, &["w"]);
+    assert_eq!(labels.len(), 3);
+    let span = labels[2].inner();
+    assert_eq!(&input[span.offset()..span.offset() + span.len()], "w");
+}
+
+#[test]
+fn test_struct_equality() {
+    let input = r#"
+        struct Inner {
+            value: int;
+        }
+
+        struct Outer {
+            name: String;
+            inner: Inner;
+        }
+
+        fn main() -> int {
+            let a = Outer { name: "a", inner: Inner { value: 1 } };
+            let b = Outer { name: "a", inner: Inner { value: 1 } };
+            let c = Outer { name: "a", inner: Inner { value: 2 } };
+            if (a == b) && (a != c) {
+                return 1;
+            }
+            return 0;
+        }
+    "#;
+
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(1));
+
+    let input = r#"
+        struct A {
+            value: int;
+        }
+
+        struct B {
+            value: int;
+        }
+
+        fn main() {
+            let equal = A { value: 1 } == B { value: 1 };
+        }
+    "#;
+
+    let module = Parser::new(input).parse_module().unwrap();
+    let err = ExecutionContext::new(&module).execute().unwrap_err();
+    assert!(err.downcast_ref::<TypeMismatch>().is_some());
+}
+
+#[test]
+fn test_nested_field_access() {
+    let input = r#"
+        struct Inner {
+            value: int;
+        }
+
+        struct Middle {
+            inner: Inner;
+        }
+
+        struct Outer {
+            middle: Middle;
+        }
+
+        fn make(value: int) -> Outer {
+            Outer { middle: Middle { inner: Inner { value: value } } }
+        }
+
+        fn main() -> int {
+            let outer = make(7);
+            let middle = outer.middle;
+            return outer.middle.inner.value + middle.inner.value + make(1).middle.inner.value;
+        }
+    "#;
+
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(15));
+}
+
+#[test]
+fn test_struct_clone_is_deep() {
+    let mut inner = StructValue::default();
+    inner.push_field("value".into(), value::Value::new_int(1).into());
+    let mut outer = StructValue::default();
+    outer.push_field(
+        "inner".into(),
+        value::Value::new_struct("Inner".into(), inner).into(),
+    );
+    let original = value::Value::new_struct("Outer".into(), outer);
+
+    let inner_value = |outer: &value::Value| {
+        let inner = &outer
+            .as_struct()
+            .unwrap()
+            .field_by_name("inner")
+            .unwrap()
+            .value;
+        inner
+            .as_struct()
+            .unwrap()
+            .field_by_name("value")
+            .unwrap()
+            .value
+            .as_int()
+    };
+
+    let mut copy = original.clone();
+    let inner = &mut copy
+        .as_struct_mut()
+        .unwrap()
+        .field_by_name_mut("inner")
+        .unwrap()
+        .value;
+    inner
+        .as_struct_mut()
+        .unwrap()
+        .field_by_name_mut("value")
+        .unwrap()
+        .value = value::Value::new_int(2);
+
+    assert_eq!(inner_value(&copy), Some(2));
+    assert_eq!(inner_value(&original), Some(1));
+}
+
+#[test]
+fn test_struct_assignment() {
+    let input = r#"
+        struct Point {
+            x: int;
+            y: int;
+        }
+
+        struct Other {
+            x: int;
+        }
+
+        fn make_point(x: int) -> Point {
+            Point { x: x, y: 0 }
+        }
+
+        fn main() -> int {
+            let p = make_point(1);
+            p = make_point(2);
+            return p.x;
+        }
+    "#;
+
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(2));
+
+    let input = input.replace("p = make_point(2);", "p = Other { x: 2 };");
+    let module = Parser::new(input.as_str()).parse_module().unwrap();
+    let err = ExecutionContext::new(&module).execute().unwrap_err();
+    assert!(err.downcast_ref::<TypeMismatch>().is_some());
+}
+
+#[test]
+fn test_fibonacci() {
+    let input = include_str!("../../fib.al");
+    assert_eq!(
+        lang::wasm::run_source(input).unwrap(),
+        " 1 1 2 3 5 8 13 21 34\n"
+    );
+}
+
+#[test]
+fn test_block_as_value() {
+    let input = r#"
+        fn add(a: int, b: int) -> int {
+            a + b
+        }
+
+        fn main() -> int {
+            let x = 1 + { let y = 2; y * 3 };
+            let z = { 1 } + { 2 };
+            let w = { 1 } + 2;
+            add({ x }, { 1 }) + z - w
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(8));
+
+    let module = Parser::new("fn main() -> int { ;; 1 }")
+        .parse_module()
+        .unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(1));
+}
+
+#[test]
+fn test_type_alias() {
+    let input = r#"
+        type Id = int;
+        type Key = Id;
+
+        fn next(id: Id) -> Key {
+            id + 1
+        }
+
+        fn main() -> Id {
+            let id: Key = next(41);
+            id
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(42));
+
+    let input = r#"
+        type Id = int;
+
+        fn main() {
+            let id: Id = "one";
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let err = ExecutionContext::new(&module).execute().unwrap_err();
+    let Some(TypeMismatch { expected, .. }) = err.downcast_ref::<TypeMismatch>() else {
+        panic!("Expected type mismatch, got {:?}", err);
+    };
+    assert_eq!(*expected, TypeID::Int);
+    assert_eq!(
+        err.to_string(),
+        "Type mismatch: found String, expected Id (alias of int)"
+    );
+
+    let input = r#"
+        type Id = int;
+
+        fn next(id: Id) -> Id {
+            return true;
+        }
+
+        fn main() {
+            next(1);
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let err = ExecutionContext::new(&module).execute().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Type mismatch: found bool, expected Id (alias of int)"
+    );
+
+    let input = r#"
+        type A = B;
+        type B = A;
+
+        fn main() {}
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let err = ExecutionContext::new(&module).execute().unwrap_err();
+    assert_eq!(err.to_string(), "Type alias 'A' refers to itself");
+}
+
+#[test]
+fn test_let_without_initializer() {
+    let input = r#"
+        fn main() -> int {
+            let x: int;
+            if true {
+                x = 1;
+            } else {
+                x = 2;
+            }
+            x
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(1));
+
+    let input = r#"
+        fn main() -> int {
+            let x: int;
+            x + 1
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let err = ExecutionContext::new(&module).execute().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Variable 'x' is used before it was assigned"
+    );
+
+    let input = r#"
+        fn main() {
+            let x: int;
+            x = "one";
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let err = ExecutionContext::new(&module).execute().unwrap_err();
+    assert!(err.downcast_ref::<TypeMismatch>().is_some());
+
+    // A type is required without an initializer
+    assert!(Parser::new("fn main() { let x; }").parse_module().is_err());
+}
+
+#[test]
+fn test_function_scope_is_isolated() {
+    let input = r#"
+        fn callee() -> int {
+            local
+        }
+
+        fn main() -> int {
+            let local = 1;
+            callee()
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let err = ExecutionContext::new(&module).execute().unwrap_err();
+    assert_eq!(err.to_string(), "Variable not found");
+}
+
+#[test]
+fn test_inferred_return_type() {
+    let input = r#"
+        fn f() {
+            1 + 1
+        }
+
+        fn g() {
+            let x = 1;
+        }
+
+        fn main() -> int {
+            g();
+            f()
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(2));
+
+    let input = r#"
+        struct Point {
+            x: int;
+        }
+
+        fn sign(n: int) {
+            if n < 0 {
+                return 0 - 1;
+            }
+            if n == 0 {
+                return 0;
+            }
+            1
+        }
+
+        fn factorial(n: int) {
+            if n == 0 { 1 } else { n * factorial(n - 1) }
+        }
+
+        fn count() {
+            let i = 0;
+            loop {
+                i += 1;
+                if i == 3 {
+                    break i;
+                }
+            }
+        }
+
+        fn x(p: Point) {
+            p.x
+        }
+
+        fn main() -> int {
+            sign(0 - 5) + factorial(4) + count() + x(Point { x: 10 })
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(36));
+
+    // All returns and the value of the body have to agree, before anything runs
+    let input = r#"
+        fn f(c: bool) {
+            if c {
+                return 1;
+            }
+            true
+        }
+
+        fn main() {}
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let err = ExecutionContext::new(&module).execute().unwrap_err();
+    let Some(TypeMismatch {
+        found, expected, ..
+    }) = err.downcast_ref::<TypeMismatch>()
+    else {
+        panic!("Expected type mismatch, got {:?}", err);
+    };
+    assert_eq!((found, expected), (&TypeID::Bool, &TypeID::Int));
+
+    let input = "fn f() { return 5; } fn main() { let x: String = f(); }";
+    let module = Parser::new(input).parse_module().unwrap();
+    let err = ExecutionContext::new(&module).execute().unwrap_err();
+    assert!(err.downcast_ref::<TypeMismatch>().is_some());
+
+    let module = Parser::new("fn main() { true }").parse_module().unwrap();
+    let err = ExecutionContext::new(&module).execute().unwrap_err();
+    let Some(InvalidMainReturnType { found, .. }) = err.downcast_ref::<InvalidMainReturnType>()
+    else {
+        panic!("Expected invalid main return type, got {:?}", err);
+    };
+    assert_eq!(*found, TypeID::Bool);
+}
+
+#[test]
+fn test_string_concatenation() {
+    let input = r#"
+        fn main() {
+            println(1 + "x");
+            println("x" + 1);
+            println(true + "x");
+            println("x" + true);
+            println(1.5 + "x");
+            println("x" + 1.5);
+            println("x" + "y");
+        }
+    "#;
+    assert_eq!(
+        lang::wasm::run_source(input).unwrap(),
+        "1x\nx1\ntruex\nxtrue\n1.5x\nx1.5\nxy\n"
+    );
+}
+
+#[test]
+fn test_typeof() {
+    let input = r#"
+        struct Point {
+            x: int;
+        }
+
+        fn main() {
+            println(typeof(3));
+            println(typeof(1.5));
+            println(typeof("x"));
+            println(typeof(Point { x: 1 }));
+        }
+    "#;
+    assert_eq!(
+        lang::wasm::run_source(input).unwrap(),
+        "int\nfloat\nString\nPoint\n"
+    );
+}
+
+#[test]
+fn test_format() {
+    let input = r#"
+        fn main() {
+            println(format("{} + {} = {}", 1, 2, 3));
+            println(format("{{}} {}", true));
+            println(format("no args"));
+        }
+    "#;
+    assert_eq!(
+        lang::wasm::run_source(input).unwrap(),
+        "1 + 2 = 3\n{} true\nno args\n"
+    );
+
+    for input in [
+        r#"fn main() { format("{} {}", 1); }"#,
+        r#"fn main() { format("{}", 1, 2); }"#,
+        r#"fn main() { format("{", 1); }"#,
+        "fn main() { format(1); }",
+        "fn main() { format(); }",
+    ] {
+        assert!(lang::wasm::run_source(input).is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn test_system_function_arity() {
+    for input in [
+        "fn main() { print(); }",
+        r#"fn main() { println("a", "b"); }"#,
+    ] {
+        let module = Parser::new(input).parse_module().unwrap();
+        let err = ExecutionContext::new(&module).execute().unwrap_err();
+        assert!(err.downcast_ref::<InvalidNumberOfArguments>().is_some());
+    }
+}
+
+#[test]
+fn test_else_if() {
+    let input = r#"
+        fn check(n: int) {
+            if n == 1 {
+                println("first");
+            } else if n == 2 {
+                println("second");
+            } else if n == 3 {
+                println("third");
+            } else {
+                println("other");
+            }
+        }
+
+        fn main() {
+            check(2);
+            check(3);
+            check(4);
+        }
+    "#;
+    assert_eq!(
+        lang::wasm::run_source(input).unwrap(),
+        "second\nthird\nother\n"
+    );
+}
+
+#[test]
+fn test_nested_if_else() {
+    let input = r#"
+        fn check(a: bool, b: bool) -> int {
+            if a { if b { return 1; } } else { return 2; }
+            3
+        }
+
+        fn main() -> int {
+            check(true, false) * 100 + check(false, true) * 10 + check(true, true)
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(321));
+}
+
+#[test]
+fn test_argument_type_mismatch_labels_parameter() {
+    let input = r#"
+        fn f(a: int, b: int, c: int) {}
+
+        fn main() {
+            f(1, "two", 3);
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let err = ExecutionContext::new(&module).execute().unwrap_err();
+    let Some(TypeMismatch {
+        span,
+        declared_span: Some(declared_span),
+        ..
+    }) = err.downcast_ref::<TypeMismatch>()
+    else {
+        panic!("Expected type mismatch with declared span, got {:?}", err);
+    };
+    assert_eq!(&input[span.offset()..span.offset() + span.len()], "\"two\"");
+    assert_eq!(
+        &input[declared_span.offset()..declared_span.offset() + declared_span.len()],
+        "int"
+    );
+}
+
+#[test]
+fn test_return_type_checked_at_return() {
+    let input = r#"
+        fn f(a: int) -> int {
+            if a > 0 {
+                if a > 1 {
+                    return "big";
+                }
+            }
+            a
+        }
+
+        fn main() -> int {
+            f(2)
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let err = ExecutionContext::new(&module).execute().unwrap_err();
+    let Some(TypeMismatch { span, .. }) = err.downcast_ref::<TypeMismatch>() else {
+        panic!("Expected type mismatch, got {:?}", err);
+    };
+    assert_eq!(&input[span.offset()..span.offset() + span.len()], "\"big\"");
+}
+
+#[test]
+fn test_cast() {
+    let module = Parser::new("fn main() -> int { let f = 3.9 as int; f + (5 as float) as int }")
+        .parse_module()
+        .unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(8));
+
+    let input = r#"fn main() { println(42 as String + "!"); }"#;
+    assert_eq!(lang::wasm::run_source(input).unwrap(), "42!\n");
+
+    let module = Parser::new("fn main() { true as float; }")
+        .parse_module()
+        .unwrap();
+    assert!(ExecutionContext::new(&module).execute().is_err());
+}
+
+#[test]
+fn test_compound_assignment() {
+    let input = r#"
+        fn double(a: int) -> int {
+            a * 2
+        }
+
+        fn main() -> int {
+            let x = 3;
+            x += double(x);
+            x -= 1;
+            x *= 3;
+            x /= 2;
+            x
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(12));
+
+    let input = r#"fn main() { let s = "a"; s += 1; println(s); }"#;
+    assert_eq!(lang::wasm::run_source(input).unwrap(), "a1\n");
+
+    let module = Parser::new("fn main() { let x: int; x += 1; }")
+        .parse_module()
+        .unwrap();
+    assert!(ExecutionContext::new(&module).execute().is_err());
+}
+
+#[test]
+fn test_methods() {
+    let input = r#"
+        struct Point {
+            x: int;
+            y: int;
+        }
+
+        impl Point {
+            fn sum(self) -> int {
+                self.x + self.y
+            }
+
+            fn scaled(self, factor: int) -> Point {
+                Point { x: self.x * factor, y: self.y * factor }
+            }
+        }
+
+        fn twice(a: int) -> int {
+            a * 2
+        }
+
+        fn main() -> int {
+            let p = Point { x: 1, y: 2 };
+            let n = 3;
+            // Methods are preferred, free functions are the fallback
+            p.scaled(10).sum() + n.twice()
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(36));
+
+    let input = "impl int { fn f(self) {} } fn main() {}";
+    let module = Parser::new(input).parse_module().unwrap();
+    assert!(ExecutionContext::new(&module).execute().is_err());
+}
+
+#[test]
+fn test_methods_in_nested_modules() {
+    let input = r#"
+        struct P {
+            x: int;
+        }
+
+        impl P {
+            fn get(self) -> int {
+                self.x
+            }
+        }
+
+        mod m {
+            impl P {
+                fn twice(self) -> int {
+                    self.get() * 2
+                }
+            }
+
+            fn f(p: P) -> int {
+                p.get() + p.twice()
+            }
+        }
+
+        fn main() -> int {
+            m::f(P { x: 5 })
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap();
+    let result = ExecutionContext::new(&module).execute().unwrap();
+    assert_eq!(result.value.as_int(), Some(15));
+}
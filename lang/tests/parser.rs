@@ -1,4 +1,14 @@
-use lang::{execution::ExecutionContext, parser::Parser};
+use lang::{
+    error::{
+        InvalidNumber, MissingSemicolon, MissingSeparator, Redefinition, ReservedKeyword,
+        UnexpectedCharacter, UnexpectedEof, UnexpectedToken,
+    },
+    module::{Module, ModuleItem},
+    parser::expression::DotExpr,
+    prelude::*,
+    validation::find_unused_variables,
+    visit::{walk_expr, Visitor},
+};
 
 #[test]
 fn test_full_language_parser() {
@@ -11,3 +21,628 @@ fn test_full_language_parser() {
         })
         .unwrap();
 }
+
+/// Prints binary expressions fully parenthesized, so we can check the shape of the tree.
+fn tree(expr: &Spanned<Expr>) -> String {
+    match &expr.value {
+        Expr::Binary(Spanned::<BinaryExpression> {
+            value: BinaryExpression { lhs, op, rhs },
+            ..
+        }) => format!("({} {} {})", tree(lhs), op.value, tree(rhs)),
+        Expr::ChainedComparison(first, comparisons) => {
+            let comparisons = comparisons
+                .iter()
+                .map(|(op, operand)| format!(" {} {}", op.value, tree(operand)))
+                .collect::<String>();
+            format!("({}{})", tree(first), comparisons)
+        }
+        other => other.to_string(),
+    }
+}
+
+fn parse_tree(input: &str) -> String {
+    tree(&Parser::new(input).parse_expression().unwrap())
+}
+
+#[test]
+fn test_logical_precedence() {
+    assert_eq!(parse_tree("a || b && c"), "(a || (b && c))");
+    assert_eq!(parse_tree("a && b || c"), "((a && b) || c)");
+    assert_eq!(parse_tree("a || b && c || d"), "((a || (b && c)) || d)");
+    assert_eq!(parse_tree("a && b && c"), "((a && b) && c)");
+}
+
+#[test]
+fn test_comparison_precedence() {
+    assert_eq!(parse_tree("a < b && c < d"), "((a < b) && (c < d))");
+    assert_eq!(parse_tree("a >= b || c != d"), "((a >= b) || (c != d))");
+    assert_eq!(parse_tree("a == b < c"), "(a == (b < c))");
+    assert_eq!(parse_tree("a + 1 < b * 2"), "((a + 1) < (b * 2))");
+}
+
+#[test]
+fn test_compound_assignment_precedence() {
+    assert_eq!(parse_tree("a += b * 2"), "(a += (b * 2))");
+    assert_eq!(parse_tree("a = b -= c"), "(a = (b -= c))");
+}
+
+#[test]
+fn test_cast_precedence() {
+    assert_eq!(parse_tree("a + b as float"), "(a + b as float)");
+    assert_eq!(parse_tree("a as float * 2.0"), "(a as float * 2.0)");
+    assert_eq!(parse_tree("a as int as float"), "a as int as float");
+}
+
+#[test]
+fn test_chained_comparison() {
+    assert_eq!(parse_tree("a < b < c"), "(a < b < c)");
+    assert_eq!(parse_tree("a <= b < c + 1 >= d"), "(a <= b < (c + 1) >= d)");
+    assert_eq!(parse_tree("a < b == c < d"), "((a < b) == (c < d))");
+    assert_eq!(parse_tree("(a < b) < c"), "((a < b) < c)");
+    assert_eq!(parse_tree("a < f() < c"), "(a < f() < c)");
+
+    let expr = Parser::new("(a < b < c) == (a && b) < c")
+        .parse_expression()
+        .unwrap();
+    assert_eq!(expr.value.to_string(), "a < b < c == (a && b) < c");
+    let expr = Parser::new("(a < b < c) < d").parse_expression().unwrap();
+    assert_eq!(expr.value.to_string(), "(a < b < c) < d");
+}
+
+#[test]
+fn test_chained_assignment() {
+    assert_eq!(parse_tree("a = b = c"), "(a = (b = c))");
+    assert_eq!(parse_tree("a = b = c + 1"), "(a = (b = (c + 1)))");
+    assert_eq!(parse_tree("a = b || c"), "(a = (b || c))");
+}
+
+#[test]
+fn test_duplicate_function() {
+    let err = Parser::new("fn foo() {} fn bar() {} fn foo() {}")
+        .parse_module()
+        .unwrap_err();
+
+    let Some(Redefinition::Function {
+        name,
+        first,
+        second,
+    }) = err.downcast_ref::<Redefinition>()
+    else {
+        panic!("Expected function redefinition, got {:?}", err);
+    };
+    assert_eq!(name, "foo");
+    assert_eq!(first.offset(), 3);
+    assert_eq!(second.offset(), 27);
+}
+
+#[test]
+fn test_duplicate_struct() {
+    let err = Parser::new("struct Foo { a: int; } struct Foo { b: int; }")
+        .parse_module()
+        .unwrap_err();
+
+    let Some(Redefinition::Struct {
+        name,
+        first,
+        second,
+    }) = err.downcast_ref::<Redefinition>()
+    else {
+        panic!("Expected struct redefinition, got {:?}", err);
+    };
+    assert_eq!(name, "Foo");
+    assert_eq!(first.offset(), 7);
+    assert_eq!(second.offset(), 30);
+}
+
+/// Displaying an expression and parsing it again has to yield the same tree
+fn assert_round_trip(input: &str) {
+    let expr = Parser::new(input).parse_expression().unwrap();
+    let displayed = expr.value.to_string();
+    let reparsed = Parser::new(displayed.as_str())
+        .parse_expression()
+        .unwrap_or_else(|e| panic!("Failed to parse '{}': {:?}", displayed, e));
+
+    assert_eq!(tree(&expr), tree(&reparsed));
+    assert_eq!(displayed, reparsed.value.to_string());
+}
+
+#[test]
+fn test_display_round_trip() {
+    assert_round_trip("(a + b) * c - (d - e)");
+    assert_round_trip("a - b - c");
+    assert_round_trip("a = b = c");
+    assert_round_trip("(a || b) && c");
+    assert_round_trip("(a + b).c");
+    assert_round_trip("Point { x: 1.0, y: \"a\\\"b\" }.x");
+    assert_round_trip(
+        "{ let x: int = (1 + 2) * 3; x = x - (4 - 5); if x > 2 { return; } loop { break; } x }",
+    );
+    assert_round_trip("{ for c in \"abc\" { print(c[0]); } return }");
+
+    let input = "fn add(a: int, b: int) -> int { a + b }";
+    let module = Parser::new(input).parse_module().unwrap();
+    let displayed = module.value.functions()[0].value.to_string();
+    assert_eq!(displayed, "fn add(a: int, b: int) -> int { a + b }");
+}
+
+#[test]
+fn test_variable_before_block() {
+    let expr = Parser::new("if x { 1 } else { 2 }")
+        .parse_expression()
+        .unwrap();
+    let Expr::IfExpression {
+        if_block: (condition, _),
+        else_block,
+        ..
+    } = expr.value
+    else {
+        panic!("Expected if expression, got {}", expr.value);
+    };
+    assert!(matches!(condition.value, Expr::Variable(_)));
+    assert!(else_block.is_some());
+
+    let expr = Parser::new("if a < b { c } else { d }")
+        .parse_expression()
+        .unwrap();
+    assert!(matches!(expr.value, Expr::IfExpression { .. }));
+
+    let expr = Parser::new("for c in text { print(c); }")
+        .parse_expression()
+        .unwrap();
+    assert!(matches!(expr.value, Expr::For { .. }));
+
+    // Struct literals still work in conditions
+    let expr = Parser::new("if Check { val: true }.val { 1 }")
+        .parse_expression()
+        .unwrap();
+    let Expr::IfExpression {
+        if_block: (condition, _),
+        ..
+    } = expr.value
+    else {
+        panic!("Expected if expression, got {}", expr.value);
+    };
+    assert!(matches!(condition.value, Expr::Dot { .. }));
+
+    // An empty block after a variable in a condition, but an empty struct literal elsewhere
+    let expr = Parser::new("if x {} else {}").parse_expression().unwrap();
+    let Expr::IfExpression {
+        if_block: (condition, _),
+        ..
+    } = expr.value
+    else {
+        panic!("Expected if expression, got {}", expr.value);
+    };
+    assert!(matches!(condition.value, Expr::Variable(_)));
+
+    let expr = Parser::new("if f(E {}) {}").parse_expression().unwrap();
+    let Expr::IfExpression {
+        if_block: (condition, _),
+        ..
+    } = expr.value
+    else {
+        panic!("Expected if expression, got {}", expr.value);
+    };
+    let Expr::FunctionCall(_, args) = condition.value else {
+        panic!("Expected function call, got {}", condition.value);
+    };
+    assert!(matches!(args[0].value, Expr::StructLiteral(..)));
+
+    let expr = Parser::new("E {}").parse_expression().unwrap();
+    assert!(matches!(expr.value, Expr::StructLiteral(..)));
+}
+
+#[test]
+fn test_nested_if_else() {
+    // The `else` after the inner block belongs to the outer `if`
+    let expr = Parser::new("if a { if b { x } } else { z }")
+        .parse_expression()
+        .unwrap();
+    let Expr::IfExpression {
+        if_block: (_, then_block),
+        else_block: Some(_),
+        ..
+    } = expr.value
+    else {
+        panic!("Expected if with else, got {}", expr.value);
+    };
+    let Expr::Block(_, Some(inner)) = then_block.value else {
+        panic!("Expected block with value, got {}", then_block.value);
+    };
+    assert!(matches!(
+        inner.value,
+        Expr::IfExpression {
+            else_block: None,
+            ..
+        }
+    ));
+
+    // An `if` inside of an else block keeps its own `else`
+    let expr = Parser::new("if a { x } else { if b { y } else { z } }")
+        .parse_expression()
+        .unwrap();
+    let Expr::IfExpression {
+        else_if_blocks,
+        else_block: Some(else_block),
+        ..
+    } = expr.value
+    else {
+        panic!("Expected if with else, got {}", expr.value);
+    };
+    assert!(else_if_blocks.is_empty());
+    let Expr::Block(_, Some(inner)) = else_block.value else {
+        panic!("Expected block with value, got {}", else_block.value);
+    };
+    assert!(matches!(
+        inner.value,
+        Expr::IfExpression {
+            else_block: Some(_),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_unexpected_token_span() {
+    let input = "fn main() { let x = while; }";
+    let err = Parser::new(input).parse_module().unwrap_err();
+    let Some(UnexpectedToken { span, .. }) = err.downcast_ref::<UnexpectedToken>() else {
+        panic!("Expected unexpected token, got {:?}", err);
+    };
+    assert_eq!(&input[span.offset()..span.offset() + span.len()], "while");
+
+    let input = "fn main(a: int b: int) {}";
+    let err = Parser::new(input).parse_module().unwrap_err();
+    let Some(UnexpectedToken { span, .. }) = err.downcast_ref::<UnexpectedToken>() else {
+        panic!("Expected unexpected token, got {:?}", err);
+    };
+    assert_eq!(&input[span.offset()..span.offset() + span.len()], "b");
+}
+
+#[test]
+fn test_impl_block() {
+    let module = Parser::new(
+        "struct Point { x: int; } impl Point { fn get_x(self) -> int { self.x } fn new(x: int) {} }",
+    )
+    .parse_module()
+    .unwrap()
+    .value;
+    let method = module.find_method("Point", "get_x").unwrap();
+    assert_eq!(
+        method.value.proto.value.to_string(),
+        "fn get_x(self: Point) -> int"
+    );
+    assert!(module.find_method("Point", "new").is_some());
+    assert!(module.find_function("get_x").is_none());
+
+    let err = Parser::new("impl P { fn a(self) {} fn a(self) {} }")
+        .parse_module()
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<Redefinition>(),
+        Some(Redefinition::Method { .. })
+    ));
+
+    // `self` needs a type outside of impl blocks
+    assert!(Parser::new("fn f(self) {}").parse_module().is_err());
+    assert!(Parser::new("impl P { struct S { x: int; } }")
+        .parse_module()
+        .is_err());
+}
+
+#[test]
+fn test_module_declaration() {
+    let module = Parser::new("mod inner { fn helper() {} } fn main() {}")
+        .parse_module()
+        .unwrap()
+        .value;
+    assert!(module.resolve_function("inner::helper").is_some());
+    assert!(module.resolve_function("helper").is_none());
+    assert!(module.resolve_function("main").is_some());
+
+    let err = Parser::new("mod a {} mod a {}").parse_module().unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<Redefinition>(),
+        Some(Redefinition::Module { .. })
+    ));
+
+    let err = Parser::new("mod a { struct S { x: int; } }")
+        .parse_module()
+        .unwrap_err();
+    let Some(UnexpectedToken { expected, .. }) = err.downcast_ref::<UnexpectedToken>() else {
+        panic!("Expected unexpected token, got {:?}", err);
+    };
+    assert_eq!(expected.to_string(), "Expected function, mod or impl");
+    assert!(Parser::new("inner::value").parse_expression().is_err());
+}
+
+#[test]
+fn test_unexpected_eof() {
+    let input = "fn f(";
+    let err = Parser::new(input).parse_module().unwrap_err();
+    let Some(UnexpectedEof { span }) = err.downcast_ref::<UnexpectedEof>() else {
+        panic!("Expected unexpected end of input, got {:?}", err);
+    };
+    assert!(!span.is_empty());
+    assert_eq!(span.offset() + span.len(), input.len());
+}
+
+#[test]
+fn test_invalid_number() {
+    let err = Parser::new("1 + 1.2.3").parse_expression().unwrap_err();
+    let Some(InvalidNumber { literal, span }) = err.downcast_ref::<InvalidNumber>() else {
+        panic!("Expected invalid number, got {:?}", err);
+    };
+    assert_eq!(literal, "1.2.3");
+    assert_eq!(*span, (4, 5).into());
+
+    assert!(Parser::new("1.").parse_expression().is_err());
+    assert!(Parser::new(".5").parse_expression().is_err());
+}
+
+#[test]
+fn test_unexpected_character() {
+    let err = Parser::new("1 + #").parse_expression().unwrap_err();
+    let Some(UnexpectedCharacter { found, span }) = err.downcast_ref::<UnexpectedCharacter>()
+    else {
+        panic!("Expected unexpected character, got {:?}", err);
+    };
+    assert_eq!(*found, '#');
+    assert_eq!(span.offset(), 4);
+
+    let err = Parser::new("fn main() {} @").parse_module().unwrap_err();
+    assert!(err.downcast_ref::<UnexpectedCharacter>().is_some());
+}
+
+#[test]
+fn test_block_as_value() {
+    assert_eq!(parse_tree("1 + { 2 }"), "(1 + { 2 })");
+    assert_eq!(parse_tree("a * { b; c }"), "(a * { b; c })");
+    assert_eq!(parse_tree("f({ 1 }, { 2 + 3 })"), "f({ 1 }, { 2 + 3 })");
+    assert_eq!(parse_tree("{ 1 } + { 2 }"), "({ 1 } + { 2 })");
+    assert_eq!(parse_tree("{ 1 } + 2 * 3"), "({ 1 } + (2 * 3))");
+    // A block at the start of a statement is not an operand
+    let err = Parser::new("{ { 1 } + 2 }").parse_expression().unwrap_err();
+    assert!(err.downcast_ref::<MissingSemicolon>().is_some());
+}
+
+#[test]
+fn test_valueless_break() {
+    // A closing parenthesis, comma or the end of the input ends `break` like a semicolon
+    assert_eq!(parse_tree("f(break)"), "f(break)");
+    assert_eq!(parse_tree("f(break, return)"), "f(break, return)");
+    assert_eq!(parse_tree("(break)"), "break");
+    assert_eq!(parse_tree("break"), "break");
+    assert_eq!(parse_tree("break x"), "break x");
+}
+
+#[test]
+fn test_empty_statements() {
+    assert_eq!(parse_tree("{ ;; 1 }"), "{ 1 }");
+    assert_eq!(parse_tree("{ a;; b; ; }"), "{ a; b; }");
+}
+
+#[test]
+fn test_reserved_keyword_as_identifier() {
+    let err = Parser::new("let loop = 5;").parse_expression().unwrap_err();
+    let Some(ReservedKeyword { keyword, span }) = err.downcast_ref::<ReservedKeyword>() else {
+        panic!("Expected reserved keyword, got {:?}", err);
+    };
+    assert_eq!(keyword, "loop");
+    assert_eq!(span.offset(), 4);
+
+    let err = Parser::new("struct break { x: int; }")
+        .parse_module()
+        .unwrap_err();
+    assert!(err.downcast_ref::<ReservedKeyword>().is_some());
+}
+
+#[test]
+fn test_type_display() {
+    for name in ["int", "float", "String", "bool", "void", "Point"] {
+        let type_id = TypeID::from_string(name);
+        assert_eq!(type_id.to_string(), name);
+        assert_eq!(TypeID::from_string(&type_id.to_string()), type_id);
+    }
+}
+
+#[test]
+fn test_missing_semicolon() {
+    let err = Parser::new("{ let a = 1 let b = 2; b }")
+        .parse_expression()
+        .unwrap_err();
+    let Some(MissingSemicolon { span }) = err.downcast_ref::<MissingSemicolon>() else {
+        panic!("Expected missing semicolon, got {:?}", err);
+    };
+    assert_eq!(span.offset(), 11);
+    assert_eq!(span.len(), 0);
+}
+
+#[test]
+fn test_missing_semicolon_in_middle_of_block() {
+    // The nested block ends with `}`, which must not be mistaken for the end of the outer block
+    let input = "{ let a = { 1 } let b = 2; b }";
+    let err = Parser::new(input).parse_expression().unwrap_err();
+    let Some(MissingSemicolon { span }) = err.downcast_ref::<MissingSemicolon>() else {
+        panic!("Expected missing semicolon, got {:?}", err);
+    };
+    assert_eq!(span.offset(), 15);
+
+    let err = Parser::new("{ loop { a = 1 break; } }")
+        .parse_expression()
+        .unwrap_err();
+    assert!(err.downcast_ref::<MissingSemicolon>().is_some());
+
+    let Expr::Block(statements, tail) = Parser::new("{ if a { 1 } loop { break; } { 2 } }")
+        .parse_expression()
+        .unwrap()
+        .value
+    else {
+        panic!("Expected block");
+    };
+    assert_eq!(statements.len(), 2);
+    assert!(matches!(tail.unwrap().value, Expr::Block(..)));
+}
+
+#[test]
+fn test_missing_separator() {
+    let err = Parser::new("{ 1 2 }").parse_expression().unwrap_err();
+    let Some(MissingSeparator { span }) = err.downcast_ref::<MissingSeparator>() else {
+        panic!("Expected missing separator, got {:?}", err);
+    };
+    assert_eq!(span.offset(), 4);
+
+    let err = Parser::new("{ a \"b\" }").parse_expression().unwrap_err();
+    assert!(err.downcast_ref::<MissingSeparator>().is_some());
+}
+
+#[test]
+fn test_duplicate_type_alias() {
+    let err = Parser::new("struct Id { x: int; } type Id = int;")
+        .parse_module()
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<Redefinition>(),
+        Some(Redefinition::Type { .. })
+    ));
+}
+
+#[test]
+fn test_parse_top_level_item() {
+    let mut module = Module::new("repl");
+    for line in [
+        "struct Point { x: int; }",
+        "fn get_x(p: Point) -> int { p.x }",
+        "type Id = int;",
+    ] {
+        let item = Parser::new(line).parse_top_level_item().unwrap();
+        module.add_item(item.value).unwrap();
+    }
+    assert_eq!(module.functions().len(), 1);
+    assert_eq!(module.structs().len(), 1);
+    assert_eq!(module.type_aliases().len(), 1);
+
+    let item = Parser::new("get_x(Point { x: 1 });")
+        .parse_top_level_item()
+        .unwrap();
+    assert!(matches!(item.value, ModuleItem::Expression(_)));
+    assert!(module.add_item(item.value).is_err());
+
+    let item = Parser::new("fn get_x() {}").parse_top_level_item().unwrap();
+    assert!(module.add_item(item.value).is_err());
+}
+
+#[test]
+fn test_doc_comments() {
+    let input = r#"
+        /// Adds one
+        /// to the input
+        fn inc(a: int) -> int {
+            /// Not attached to anything
+            a + 1
+        }
+
+        // A normal comment
+        fn main() {}
+
+        /// A point
+        struct Point {
+            x: int;
+        }
+
+        impl Point {
+            /// The x coordinate
+            fn x(self) -> int {
+                self.x
+            }
+        }
+        /// Trailing
+    "#;
+    let module = Parser::new(input).parse_module().unwrap().value;
+    let inc = module.find_function("inc").unwrap();
+    assert_eq!(inc.value.doc_comments, vec!["Adds one", "to the input"]);
+    assert!(module
+        .find_function("main")
+        .unwrap()
+        .value
+        .doc_comments
+        .is_empty());
+    let (_, point) = module.find_struct("Point").unwrap();
+    assert_eq!(point.value.doc_comments, vec!["A point"]);
+    let x = module.find_method("Point", "x").unwrap();
+    assert_eq!(x.value.doc_comments, vec!["The x coordinate"]);
+}
+
+#[test]
+fn test_unused_variables() {
+    let input = r#"
+        fn main() {
+            let used = 1;
+            let unused = 2;
+            let assigned_only = 3;
+            let _ignored = 4;
+            assigned_only = used;
+            for c in "ab" {
+                let inner = c;
+            }
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap().value;
+    let unused = find_unused_variables(&module);
+    let names = unused
+        .iter()
+        .map(|warning| warning.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["inner", "unused", "assigned_only"]);
+    let span = unused[1].span;
+    assert_eq!(&input[span.offset()..span.offset() + span.len()], "unused");
+}
+
+#[test]
+fn test_visitor() {
+    /// Counts every function call, including method calls.
+    #[derive(Default)]
+    struct CallCounter {
+        calls: usize,
+    }
+
+    impl<'ast> Visitor<'ast> for CallCounter {
+        fn visit_expr(&mut self, expr: &'ast Spanned<Expr>) {
+            if let Expr::FunctionCall(..)
+            | Expr::Dot {
+                rhs:
+                    Spanned::<DotExpr> {
+                        value: DotExpr::FunctionCall(..),
+                        ..
+                    },
+                ..
+            } = expr.value
+            {
+                self.calls += 1;
+            }
+            walk_expr(self, expr);
+        }
+    }
+
+    let input = r#"
+        struct P { x: int; }
+        impl P {
+            fn get(self) -> int { id(self.x) }
+        }
+        fn id(a: int) -> int { a }
+        mod inner {
+            fn f() { println(1); }
+        }
+        fn main() {
+            let p = P { x: id(1) };
+            for c in "ab" {
+                if p.get() > 0 { println(c); }
+            }
+            println(id(id(2)));
+        }
+    "#;
+    let module = Parser::new(input).parse_module().unwrap().value;
+    let mut counter = CallCounter::default();
+    counter.visit_module(&module);
+    assert_eq!(counter.calls, 8);
+}
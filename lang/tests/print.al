@@ -0,0 +1,11 @@
+struct Point {
+    x: int;
+    y: int;
+}
+
+fn main() {
+    println(42);
+    println(true);
+    print(1.5);
+    println(Point { x: 1, y: 2 });
+}
@@ -0,0 +1,2 @@
+fn f(n: int) -> int { f(n + 1) }
+fn main() -> int { f(0) }
@@ -1,4 +1,7 @@
-use lang::tokenizer::{identifier::Identifier, literal::Literal, token::Token, Tokenizer};
+use lang::tokenizer::{
+    identifier::Identifier, literal::Literal, token::Token, tokenize, tokenize_with_comments,
+    Tokenizer,
+};
 
 const INPUT_FUNCTION_CALL: &str = "function_call()";
 const INPUT_FUNCTION_CALL_ERR1: &str = "function_call(";
@@ -141,6 +144,20 @@ fn test_string_literal() {
     );
 }
 
+#[test]
+fn test_string_line_continuation() {
+    for input in ["\"Hello, \\\n    World!\" x", "\"Hello, \\\r\n\tWorld!\" x"] {
+        let tokens = Tokenizer::new(input).collect::<Vec<_>>();
+        assert_eq!(
+            tokens[0].value,
+            Token::Literal(Literal::String("Hello, World!".to_string()))
+        );
+        let x = input.find('x').unwrap();
+        assert_eq!(tokens[0].span, (0, x - 1).into());
+        assert_eq!(tokens[1].span, (x, 1).into());
+    }
+}
+
 #[test]
 fn test_float_literal() {
     let tokens = Tokenizer::new("32.0").map(|t| t.value).collect::<Vec<_>>();
@@ -213,3 +230,79 @@ fn test_small_tokens() {
     assert_eq!(tokens.next(), Some(Token::Identifier(Identifier::LParen)));
     assert_eq!(tokens.next(), Some(Token::Identifier(Identifier::RParen)));
 }
+
+#[test]
+fn test_invalid_character() {
+    let tokens = Tokenizer::new("a # b").collect::<Vec<_>>();
+    assert_eq!(tokens.len(), 3);
+    assert_eq!(tokens[1].value, Token::Invalid('#'));
+    assert_eq!(tokens[1].span, (2, 1).into());
+    assert_eq!(
+        tokens[2].value,
+        Token::Identifier(Identifier::UserDefined("b".to_string()))
+    );
+}
+
+#[test]
+fn test_block_comment_with_partial_delimiter() {
+    let tokens = Tokenizer::new("/* a * b */ x").collect::<Vec<_>>();
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(
+        tokens[0].value,
+        Token::Identifier(Identifier::UserDefined("x".to_string()))
+    );
+    assert_eq!(tokens[0].span, (12, 1).into());
+
+    let tokens = Tokenizer::new("/* a **/ x").collect::<Vec<_>>();
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].span, (9, 1).into());
+}
+
+#[test]
+fn test_malformed_numbers() {
+    for number in ["1.2.3", "1.", "1..2", "99999999999999999999"] {
+        let tokens = tokenize(number);
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].value, Token::InvalidNumber(number.to_string()));
+        assert_eq!(tokens[0].span, (0, number.len()).into());
+    }
+
+    let tokens = tokenize("1.5 .5");
+    assert_eq!(tokens[0].value, Token::Literal(Literal::NumberFloat(1.5)));
+    assert_eq!(tokens[1].value, Token::Identifier(Identifier::Dot));
+    assert_eq!(tokens[2].value, Token::Literal(Literal::NumberInt(5)));
+}
+
+#[test]
+fn test_interleaved_comments_and_whitespace() {
+    let tokens = tokenize("x /* c */   y");
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[1].span, (12, 1).into());
+
+    let source = "x /* a */\n  // b\n\t/* c */ /* d */\n   y";
+    let tokens = tokenize(source);
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[1].span, (source.len() - 1, 1).into());
+}
+
+#[test]
+fn test_tokenize_with_comments() {
+    let source = "// line\nx /* block */ y";
+    assert_eq!(tokenize(source).len(), 2);
+
+    let tokens = tokenize_with_comments(source);
+    assert_eq!(tokens.len(), 4);
+    assert_eq!(tokens[0].value, Token::Comment("// line".to_string()));
+    assert_eq!(tokens[0].span, (0, 7).into());
+    assert_eq!(tokens[2].value, Token::Comment("/* block */".to_string()));
+    assert_eq!(tokens[2].span, (10, 11).into());
+    assert_eq!(tokens[3].span, (22, 1).into());
+}
+
+#[test]
+fn test_doc_comment() {
+    let tokens = tokenize("/// Docs\n//// not docs\n// nothing\nx");
+    assert_eq!(tokens.len(), 2);
+    assert_eq!(tokens[0].value, Token::DocComment("Docs".to_string()));
+    assert_eq!(tokens[0].span, (0, 8).into());
+}
@@ -1,16 +1,42 @@
-/// This crate provides a `ResetIterator` type that wraps an iterator and allows you to reset it
-/// to a set location. This is useful when you want to peek at the next few items in the iterator
-/// without consuming them.
-///
-/// # Example
+//! This crate provides a `ResetIterator` type that wraps an iterator and allows you to reset it
+//! to a set location. This is useful when you want to peek at the next few items in the iterator
+//! without consuming them.
+//!
+//! # Example
+use std::fmt::Display;
 
 pub struct ResetIterator<I: Iterator> {
     iter: I,
     pub(self) peeked: Vec<I::Item>,
     /// Empty if we have no peeked
     pointers: Vec<usize>,
+    /// Number of items that were removed from the front of `peeked`
+    drained: usize,
 }
 
+/// An absolute read position, created by [`ResetIterator::mark`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Mark(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreError {
+    /// The mark is ahead of the current read position.
+    Forward,
+    /// The items at the mark were already dropped from the peeked list by [`ResetIterator::push_end`].
+    Drained,
+}
+
+impl Display for RestoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RestoreError::Forward => write!(f, "Can't restore to a position that was not read yet"),
+            RestoreError::Drained => write!(f, "Can't restore to a position that was drained"),
+        }
+    }
+}
+
+impl std::error::Error for RestoreError {}
+
 impl<I: Iterator> ResetIterator<I> {
     /// Peeks at the next item in the iterator without consuming it
     /// The next call to `peek` or `next` will return the same item
@@ -74,6 +100,7 @@ impl<I: Iterator> ResetIterator<I> {
         if self.pointers.len() == 1 {
             let current_pointer = self.pointers.first().expect("Checked for 1 pointer");
             self.peeked.drain(..*current_pointer);
+            self.drained += *current_pointer;
             self.pointers[0] = 0;
             self.pointers.push(0);
         } else if !self.pointers.is_empty() {
@@ -103,6 +130,30 @@ impl<I: Iterator> ResetIterator<I> {
             self.pointers.remove(self.pointers.len() - 2);
         }
     }
+
+    /// Returns the current read position. Unlike `push_end` this doesn't need to be undone.
+    pub fn mark(&self) -> Mark {
+        Mark(self.drained + self.pointers.last().copied().unwrap_or(0))
+    }
+
+    /// Jumps back to a position returned by `mark`.
+    /// # Errors
+    /// Only already read items can be jumped to. Returns `RestoreError::Forward` if the mark is
+    /// ahead of the current position, and `RestoreError::Drained` if `push_end` dropped the items
+    /// at the mark in the meantime.
+    pub fn restore(&mut self, mark: Mark) -> Result<(), RestoreError> {
+        if mark > self.mark() {
+            return Err(RestoreError::Forward);
+        }
+        let index = mark
+            .0
+            .checked_sub(self.drained)
+            .ok_or(RestoreError::Drained)?;
+        if let Some(pointer) = self.pointers.last_mut() {
+            *pointer = index;
+        }
+        Ok(())
+    }
 }
 
 impl<T, I> From<T> for ResetIterator<I>
@@ -115,6 +166,7 @@ where
             iter: iter.into_iter(),
             peeked: vec![],
             pointers: vec![],
+            drained: 0,
         }
     }
 }
@@ -133,7 +185,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::ResetIterator;
+    use crate::{ResetIterator, RestoreError};
 
     #[test]
     fn try_out() {
@@ -230,4 +282,50 @@ mod tests {
         assert_eq!(iter.peeked.len(), 3);
         assert_eq!(iter.consume(), Some(&&5));
     }
+
+    #[test]
+    fn test_mark_restore() {
+        const ITER: &[i32] = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+        let mut iter = ResetIterator::from(ITER);
+
+        let start = iter.mark();
+        assert_eq!(iter.consume(), Some(&&0));
+        let mark = iter.mark();
+        assert_eq!(iter.consume(), Some(&&1));
+        assert_eq!(iter.consume(), Some(&&2));
+        assert_eq!(iter.consume(), Some(&&3));
+        iter.restore(mark).unwrap();
+        assert_eq!(iter.peek(), Some(&&1));
+        assert_eq!(iter.consume(), Some(&&1));
+        iter.restore(start).unwrap();
+        assert_eq!(iter.consume(), Some(&&0));
+
+        assert_eq!(iter.consume(), Some(&&1));
+        let ahead = iter.mark();
+        iter.restore(mark).unwrap();
+        assert_eq!(iter.restore(ahead), Err(RestoreError::Forward));
+        assert_eq!(iter.consume(), Some(&&1));
+    }
+
+    #[test]
+    fn test_restore_drained() {
+        const ITER: &[i32] = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+        let mut iter = ResetIterator::from(ITER);
+
+        assert_eq!(iter.consume(), Some(&&0));
+        let mark = iter.mark();
+        assert_eq!(iter.consume(), Some(&&1));
+        assert_eq!(iter.consume(), Some(&&2));
+        iter.push_end();
+        assert_eq!(iter.restore(mark), Err(RestoreError::Drained));
+
+        // Marks after the drained items still work
+        let mark = iter.mark();
+        assert_eq!(iter.consume(), Some(&&3));
+        assert_eq!(iter.consume(), Some(&&4));
+        iter.restore(mark).unwrap();
+        assert_eq!(iter.consume(), Some(&&3));
+    }
 }
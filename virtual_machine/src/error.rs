@@ -4,11 +4,13 @@ pub type VMResult<T> = std::result::Result<T, VMError>;
 
 #[derive(Error, Debug)]
 pub enum VMError {
-    #[error("Failed to read memory at {0:X}")]
-    FailedToReadMemory(u32),
+    /// `address` is the byte address, even if the memory is addressed in words.
+    #[error("Failed to read memory at {address:#06X}")]
+    FailedToReadMemory { address: u64 },
 
-    #[error("Failed to write memory at {0:X}")]
-    FailedToWriteMemory(u32),
+    /// `address` is the byte address, even if the memory is addressed in words.
+    #[error("Failed to write memory at {address:#06X}")]
+    FailedToWriteMemory { address: u64 },
 
     #[error("Failed to parse from instruction {0:X}")]
     FailedParsingInstruction(u32),
@@ -18,4 +20,23 @@ pub enum VMError {
 
     #[error("Invalid register {0:X}")]
     InvalidRegister(u8),
+
+    #[error("Program needs {needed} words of memory, but only {available} are available")]
+    ProgramTooLarge { needed: u64, available: u32 },
+}
+
+impl VMError {
+    /// Read error for the word at `address` and the byte `byte` inside of it.
+    pub fn read_failed(address: u32, byte: u32) -> Self {
+        Self::FailedToReadMemory {
+            address: address as u64 * 4 + byte as u64,
+        }
+    }
+
+    /// Write error for the word at `address` and the byte `byte` inside of it.
+    pub fn write_failed(address: u32, byte: u32) -> Self {
+        Self::FailedToWriteMemory {
+            address: address as u64 * 4 + byte as u64,
+        }
+    }
 }
@@ -31,6 +31,13 @@ pub trait InstructionPart {
     }
 
     fn into_instruction(instruction: &mut u32, bit_offset: u32, data: Self::Output) {
+        // Writing past the 32 bits would silently shift parts of the data out of the instruction
+        assert!(
+            bit_offset + Self::BIT_SIZE <= 32,
+            "Instruction overflow: writing {} bits at offset {} exceeds 32 bits",
+            Self::BIT_SIZE,
+            bit_offset
+        );
         *instruction |= Self::match_to_bytes(data) << (32 - (Self::BIT_SIZE + bit_offset));
     }
 }
@@ -112,3 +119,30 @@ pub fn load(reader: &mut InstructionReader, vm: &mut Machine) -> VMResult<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let instruction = InstructionWriter::new(OpCode::Load)
+            .write::<Register>(Register::RA2)
+            .write::<Arg20>(0xABCDE)
+            .finish();
+
+        let mut reader = InstructionReader::new(instruction);
+        assert!(matches!(reader.read::<OpCode>(), Ok(OpCode::Load)));
+        assert!(matches!(reader.read::<Register>(), Ok(Register::RA2)));
+        assert_eq!(reader.read::<Arg20>().unwrap(), 0xABCDE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Instruction overflow")]
+    fn test_over_packed_instruction() {
+        InstructionWriter::new(OpCode::Load)
+            .write::<Register>(Register::RA1)
+            .write::<Arg20>(0)
+            .write::<Register>(Register::RA1);
+    }
+}
@@ -13,7 +13,7 @@
 //! │     OPCODE    │    REG    │              OTHER ARGS           │
 //! └───────────────┴───────────┴───────────────────────────────────┘
 
-use error::VMResult;
+use error::{VMError, VMResult};
 use instruction::InstructionReader;
 use memory::Memory;
 use opcode::OpCode;
@@ -42,21 +42,45 @@ impl Machine {
         res
     }
 
+    /// Creates a machine backed by `words` zeroed words of memory.
+    /// Programs start at address 3000, so the memory has to be larger than that to run anything.
+    pub fn with_memory_size(words: usize) -> Machine {
+        Self::new(vec![0u32; words])
+    }
+
+    /// Writes `program` to memory, starting at the current instruction pointer.
+    /// # Errors
+    /// Returns `ProgramTooLarge` without writing anything if the program doesn't fit into memory.
+    pub fn load_program(&mut self, program: &[u32]) -> VMResult<()> {
+        let start = self.registers.get(Register::IP);
+        let needed = start as u64 + program.len() as u64;
+        if needed > self.memory.size() as u64 {
+            return Err(VMError::ProgramTooLarge {
+                needed,
+                available: self.memory.size(),
+            });
+        }
+        for (offset, word) in (0..).zip(program) {
+            self.memory.write(start + offset, *word)?;
+        }
+        Ok(())
+    }
+
     pub fn reset_registers(&mut self) {
         self.registers = RegisterStore::default();
         self.registers.set(Register::IP, 3000);
     }
 
     pub fn run(&mut self) -> VMResult<()> {
-        while !self.halt {
-            self.step()?;
-        }
+        while self.step()? {}
         Ok(())
     }
 
-    fn step(&mut self) -> VMResult<()> {
+    /// Runs a single instruction, so the machine can be driven from outside.
+    /// Returns `false` once the machine halted. Stepping a halted machine does nothing.
+    pub fn step(&mut self) -> VMResult<bool> {
         if self.halt {
-            return Ok(());
+            return Ok(false);
         }
 
         let instruction_pointer = self.registers.get(Register::IP);
@@ -66,7 +90,7 @@ impl Machine {
 
         self.run_instruction(&instruction)?;
 
-        Ok(())
+        Ok(!self.halt)
     }
 
     fn run_instruction(&mut self, instruction: &u32) -> VMResult<()> {
@@ -82,6 +106,19 @@ impl Machine {
         }
     }
 
+    /// Reads `len` words starting at `start`.
+    /// Memory is addressed in words, so every address is aligned.
+    /// # Errors
+    /// Returns `FailedToReadMemory` if any address of the range is out of bounds.
+    pub fn read_range(&self, start: u32, len: u32) -> VMResult<Vec<u32>> {
+        let end = start
+            .checked_add(len)
+            .ok_or(VMError::read_failed(start, 0))?;
+        (start..end)
+            .map(|address| self.memory.read(address))
+            .collect()
+    }
+
     pub fn registers(&self) -> &RegisterStore {
         &self.registers
     }
@@ -91,7 +128,16 @@ impl Machine {
     }
 }
 
+/// Sign extends the lowest `from` bits of `value` to 32 bits.
+/// A width of 0 has no sign bit and returns 0, widths of 32 or more return `value` unchanged.
 pub(crate) fn sign_extend(value: u32, from: u32) -> u32 {
+    if from == 0 {
+        return 0;
+    }
+    if from >= 32 {
+        return value;
+    }
+
     if (value >> (from - 1)) & 1 != 0 {
         value | (0xffffffff << from)
     } else {
@@ -102,6 +148,8 @@ pub(crate) fn sign_extend(value: u32, from: u32) -> u32 {
 #[cfg(test)]
 mod test {
     use super::*;
+    use instruction::{Arg20, InstructionWriter};
+
     #[test]
     fn test_sign_extend() {
         assert_eq!(
@@ -109,4 +157,105 @@ mod test {
             -1
         );
     }
+
+    #[test]
+    fn test_sign_extend_boundaries() {
+        assert_eq!(sign_extend(0xffff_ffff, 0), 0);
+        assert_eq!(sign_extend(1, 1) as i32, -1);
+        assert_eq!(sign_extend(0x7fff_ffff, 31) as i32, -1);
+        assert_eq!(sign_extend(0x8000_0000, 32), 0x8000_0000);
+        assert_eq!(sign_extend(0x1234, 40), 0x1234);
+    }
+
+    #[test]
+    fn test_read_range() {
+        let program = [
+            InstructionWriter::new(OpCode::Nop).finish(),
+            InstructionWriter::new(OpCode::Load)
+                .write::<Register>(Register::RA1)
+                .write::<Arg20>(42)
+                .finish(),
+            InstructionWriter::new(OpCode::Halt).finish(),
+        ];
+
+        let mut memory = vec![0u32; 3010];
+        memory[3000..3003].copy_from_slice(&program);
+        let machine = Machine::new(memory);
+
+        assert_eq!(machine.read_range(3000, 3).unwrap(), program);
+        assert!(machine.read_range(3008, 3).is_err());
+        assert!(machine.read_range(u32::MAX, 2).is_err());
+    }
+
+    #[test]
+    fn test_small_memory() {
+        let mut machine = Machine::with_memory_size(3004);
+        machine
+            .load_program(&[
+                InstructionWriter::new(OpCode::Load)
+                    .write::<Register>(Register::RA1)
+                    .write::<Arg20>(1)
+                    .finish(),
+                InstructionWriter::new(OpCode::Halt).finish(),
+                42,
+            ])
+            .unwrap();
+        machine.run().unwrap();
+
+        assert_eq!(machine.registers().get(Register::RA1), 42);
+        assert!(machine.read_range(3004, 1).is_err());
+    }
+
+    #[test]
+    fn test_step() {
+        let mut machine = Machine::with_memory_size(3010);
+        machine
+            .load_program(&[
+                InstructionWriter::new(OpCode::Nop).finish(),
+                InstructionWriter::new(OpCode::Load)
+                    .write::<Register>(Register::RA1)
+                    .write::<Arg20>(1)
+                    .finish(),
+                InstructionWriter::new(OpCode::Halt).finish(),
+                7,
+            ])
+            .unwrap();
+
+        assert!(machine.step().unwrap());
+        assert_eq!(machine.registers().get(Register::IP), 3001);
+        assert_eq!(machine.registers().get(Register::RA1), 0);
+
+        assert!(machine.step().unwrap());
+        assert_eq!(machine.registers().get(Register::IP), 3002);
+        assert_eq!(machine.registers().get(Register::RA1), 7);
+
+        assert!(!machine.step().unwrap());
+        assert_eq!(machine.registers().get(Register::IP), 3003);
+
+        // Halted machines stay where they are
+        assert!(!machine.step().unwrap());
+        assert_eq!(machine.registers().get(Register::IP), 3003);
+    }
+
+    #[test]
+    fn test_memory_error_reports_byte_address() {
+        let err = vec![0u32; 4].read(3000).unwrap_err();
+        assert_eq!(err.to_string(), "Failed to read memory at 0x2EE0");
+
+        let err = vec![0u8; 4].write1(1, 2, 0xFF).unwrap_err();
+        assert_eq!(err.to_string(), "Failed to write memory at 0x0006");
+    }
+
+    #[test]
+    fn test_program_too_large() {
+        let mut machine = Machine::with_memory_size(3001);
+        assert!(matches!(
+            machine.load_program(&[0, 0]),
+            Err(VMError::ProgramTooLarge {
+                needed: 3002,
+                available: 3001
+            })
+        ));
+        assert!(machine.load_program(&[0]).is_ok());
+    }
 }
@@ -3,12 +3,15 @@ use crate::error::{VMError, VMResult};
 /// For byte memory trait.
 /// read1 will read the n'th byte from the address. The input must by 0 <= n < 4.
 pub trait Memory {
+    /// Number of addressable words
+    fn size(&self) -> u32;
+
     fn read(&self, address: u32) -> VMResult<u32>;
     fn write(&mut self, address: u32, value: u32) -> VMResult<()>;
 
     fn read1(&self, address: u32, byte: u8) -> VMResult<u8> {
         if byte >= 4 {
-            return Err(VMError::FailedToReadMemory(address));
+            return Err(VMError::read_failed(address, byte as u32));
         }
         let value = self.read(address)?;
         let shift = byte * 8;
@@ -17,7 +20,7 @@ pub trait Memory {
 
     fn write1(&mut self, address: u32, byte: u8, value: u8) -> VMResult<()> {
         if byte >= 4 {
-            return Err(VMError::FailedToWriteMemory(address));
+            return Err(VMError::write_failed(address, byte as u32));
         }
         let shift = byte * 8;
         let mask = 0xFF << shift;
@@ -28,7 +31,7 @@ pub trait Memory {
 
     fn read2(&self, address: u32, half: u8) -> VMResult<u16> {
         if half >= 2 {
-            return Err(VMError::FailedToReadMemory(address));
+            return Err(VMError::read_failed(address, half as u32 * 2));
         }
         let value = self.read(address)?;
         let shift = half * 16;
@@ -37,7 +40,7 @@ pub trait Memory {
 
     fn write2(&mut self, address: u32, half: u8, value: u16) -> VMResult<()> {
         if half >= 2 {
-            return Err(VMError::FailedToWriteMemory(address));
+            return Err(VMError::write_failed(address, half as u32 * 2));
         }
         let shift = half * 16;
         let mask = 0xFFFF << shift;
@@ -67,15 +70,19 @@ pub trait Memory {
 }
 
 impl Memory for Vec<u32> {
+    fn size(&self) -> u32 {
+        self.len() as u32
+    }
+
     fn read(&self, address: u32) -> VMResult<u32> {
         self.get(address as usize)
             .copied()
-            .ok_or(VMError::FailedToReadMemory(address))
+            .ok_or(VMError::read_failed(address, 0))
     }
 
     fn write(&mut self, address: u32, value: u32) -> VMResult<()> {
         if address as usize >= self.len() {
-            return Err(VMError::FailedToWriteMemory(address));
+            return Err(VMError::write_failed(address, 0));
         }
         self[address as usize] = value;
         Ok(())
@@ -83,30 +90,42 @@ impl Memory for Vec<u32> {
 }
 
 impl Memory for Vec<u8> {
+    fn size(&self) -> u32 {
+        (self.len() / 4) as u32
+    }
+
     fn read(&self, address: u32) -> VMResult<u32> {
-        let address = (address * 4) as usize;
+        let start = address as usize * 4;
         let value = self
-            .get(address..address + 4)
-            .ok_or(VMError::FailedToReadMemory(address as u32))?;
+            .get(start..start + 4)
+            .ok_or(VMError::read_failed(address, 0))?;
         Ok(u32::from_le_bytes(value.try_into().unwrap()))
     }
 
     fn write(&mut self, address: u32, value: u32) -> VMResult<()> {
-        let address = (address * 4) as usize;
-        self[address..address + 4].copy_from_slice(&value.to_le_bytes());
+        let start = address as usize * 4;
+        self.get_mut(start..start + 4)
+            .ok_or(VMError::write_failed(address, 0))?
+            .copy_from_slice(&value.to_le_bytes());
         Ok(())
     }
 
     fn read1(&self, address: u32, byte: u8) -> VMResult<u8> {
-        let address = (address * 4 + byte as u32) as usize;
-        self.get(address)
+        if byte >= 4 {
+            return Err(VMError::read_failed(address, byte as u32));
+        }
+        self.get(address as usize * 4 + byte as usize)
             .copied()
-            .ok_or(VMError::FailedToReadMemory(address as u32))
+            .ok_or(VMError::read_failed(address, byte as u32))
     }
 
     fn write1(&mut self, address: u32, byte: u8, value: u8) -> VMResult<()> {
-        let address = (address * 4 + byte as u32) as usize;
-        self[address] = value;
+        if byte >= 4 {
+            return Err(VMError::write_failed(address, byte as u32));
+        }
+        *self
+            .get_mut(address as usize * 4 + byte as usize)
+            .ok_or(VMError::write_failed(address, byte as u32))? = value;
         Ok(())
     }
 }
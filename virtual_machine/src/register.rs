@@ -1,5 +1,6 @@
-use std::fmt::Display;
+use std::cmp::Ordering;
 
+#[cfg(feature = "std")]
 use prettytable::row;
 
 use crate::{
@@ -117,6 +118,9 @@ impl RegisterStore {
         };
     }
 
+    /// Sets the condition flag from the value in `value`.
+    /// The value is interpreted as a signed integer, so any value with bit 31 set is `Negative`.
+    /// Use [`RegisterStore::compare_unsigned`] to compare unsigned values.
     pub fn update_condition(&mut self, value: Register) {
         if self.get(value) == 0 {
             self.cond = ConditionFlag::Zero as u32;
@@ -126,9 +130,20 @@ impl RegisterStore {
             self.cond = ConditionFlag::Positive as u32;
         }
     }
+
+    /// Sets the condition flag by comparing `lhs` and `rhs` as unsigned integers.
+    /// `Zero` if both are equal, `Negative` if `lhs < rhs` and `Positive` if `lhs > rhs`.
+    pub fn compare_unsigned(&mut self, lhs: Register, rhs: Register) {
+        self.cond = match self.get(lhs).cmp(&self.get(rhs)) {
+            Ordering::Equal => ConditionFlag::Zero,
+            Ordering::Less => ConditionFlag::Negative,
+            Ordering::Greater => ConditionFlag::Positive,
+        } as u32;
+    }
 }
 
-impl Display for RegisterStore {
+#[cfg(feature = "std")]
+impl std::fmt::Display for RegisterStore {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         use prettytable::{Cell, Row, Table};
 
@@ -198,3 +213,37 @@ impl Display for RegisterStore {
         write!(f, "{}", table)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_compare_unsigned() {
+        let mut registers = RegisterStore::default();
+        registers.set(Register::RA1, 0x8000_0000);
+        registers.set(Register::RA2, 1);
+
+        // Signed, bit 31 makes the value negative
+        registers.update_condition(Register::RA1);
+        assert_eq!(
+            registers.get(Register::Cond),
+            ConditionFlag::Negative as u32
+        );
+
+        registers.compare_unsigned(Register::RA1, Register::RA2);
+        assert_eq!(
+            registers.get(Register::Cond),
+            ConditionFlag::Positive as u32
+        );
+
+        registers.compare_unsigned(Register::RA2, Register::RA1);
+        assert_eq!(
+            registers.get(Register::Cond),
+            ConditionFlag::Negative as u32
+        );
+
+        registers.compare_unsigned(Register::RA1, Register::RA1);
+        assert_eq!(registers.get(Register::Cond), ConditionFlag::Zero as u32);
+    }
+}